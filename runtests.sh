@@ -1,4 +1,4 @@
-TESTS="stored fixed dynamic mixed"
+TESTS="stored fixed dynamic mixed multi extra hcrc comment bgzf text"
 cargo build
 for i in $TESTS; do gzip -dc tests/$i.gz> tests/$i.old; done
 for a in {0..1}; do 
@@ -16,3 +16,273 @@ for a in {0..1}; do
     done
   done
 done
+for a in {0..1}; do
+  for b in {0..3}; do
+    PARAM="-f1 -b$b -a$a"
+    echo Testing $PARAM: zlib
+    ./target/debug/rgzip $PARAM tests/zlib.zz tests/zlib.new > /dev/null
+    diff -q tests/dynamic.old tests/zlib.new
+    echo Testing $PARAM: zdict
+    ./target/debug/rgzip $PARAM -D tests/dynamic.old \
+      tests/zdict.zz tests/zdict.new > /dev/null
+    diff -q tests/dynamic.old tests/zdict.new
+    echo Testing $PARAM: window
+    ./target/debug/rgzip $PARAM tests/window.zz tests/window.new > /dev/null
+    diff -q tests/dynamic.old tests/window.new
+    echo Testing -f2 -b$b -a$a: raw
+    ./target/debug/rgzip -f2 -b$b -a$a \
+      tests/raw.deflate tests/raw.new > /dev/null
+    diff -q tests/dynamic.old tests/raw.new
+    for i in zlib.zz raw.deflate dynamic.gz; do
+      echo Testing -f4 -b$b -a$a: $i
+      ./target/debug/rgzip -f4 -b$b -a$a tests/$i tests/auto.new > /dev/null
+      diff -q tests/dynamic.old tests/auto.new
+    done
+  done
+done
+echo Testing zlib errors
+./target/debug/rgzip -f1 tests/zdict.zz tests/zdict.new \
+  | grep "Stream requires a preset dictionary" > /dev/null || echo Failed
+./target/debug/rgzip -f1 -D tests/fixed.old tests/zdict.zz tests/zdict.new \
+  | grep "Preset dictionary does not match DICTID" > /dev/null || echo Failed
+(head -c -1 tests/zlib.zz; printf '\0') > tests/adler.zz.new
+./target/debug/rgzip -f1 tests/adler.zz.new tests/zlib.new \
+  | grep "ADLER32 of decoded data does not match trailer" > /dev/null \
+  || echo Failed
+# Same stream as zlib.zz, with a 256 byte window in CINFO.
+(printf '\x08\xd7'; tail -c +3 tests/zlib.zz) > tests/small.zz.new
+for b in {0..3}; do
+  ./target/debug/rgzip -f1 -b$b tests/small.zz.new tests/zlib.new \
+    | grep "Invalid deflate stream" > /dev/null || echo Failed
+done
+# longfixed.gz has fixed blocks with lengths 115 to 258, symbols 280 to 285.
+for b in {0..3}; do
+  echo Testing -b$b: longfixed
+  ./target/debug/rgzip -b$b tests/longfixed.gz tests/longfixed.new > /dev/null
+  gzip -dc tests/longfixed.gz | cmp - tests/longfixed.new
+done
+for a in {0..1}; do
+  for b in {0..3}; do
+    for i in dynamic.Z clear.Z dynamic.z lzh.Z dynamic.lzh; do
+      echo Testing -f3 -b$b -a$a: $i
+      ./target/debug/rgzip -f3 -b$b -a$a tests/$i tests/compress.new > /dev/null
+      diff -q tests/dynamic.old tests/compress.new
+    done
+  done
+done
+# The LZH archives use header levels 0, 1 and 2.
+for i in stored fixed dynamic; do
+  echo Testing lzh: $i.lzh
+  ./target/debug/rgzip tests/$i.lzh tests/lzh.new > /dev/null
+  diff -q tests/$i.old tests/lzh.new
+done
+cat tests/dynamic.old tests/dynamic.old > tests/deflate64.old
+for a in {0..1}; do
+  for b in {0..3}; do
+    echo Testing -f5 -b$b -a$a: deflate64
+    ./target/debug/rgzip -f5 -b$b -a$a \
+      tests/deflate64.deflate tests/deflate64.new > /dev/null
+    diff -q tests/deflate64.old tests/deflate64.new
+  done
+done
+for a in {0..1}; do
+  for b in {0..3}; do
+    for i in archive.zip zip64.zip; do
+      echo Testing -z -b$b -a$a: $i
+      rm -rf tests/zip.new
+      mkdir tests/zip.new
+      ./target/debug/rgzip -z -b$b -a$a tests/$i tests/zip.new > /dev/null
+      diff -q tests/dynamic.old tests/zip.new/docs/dynamic.txt
+      diff -q tests/fixed.old tests/zip.new/fixed.txt
+    done
+  done
+done
+rm -rf tests/zip.new
+# Same archive, with the compressed size of docs/dynamic.txt in the central
+# directory one byte over and one byte under the deflate stream.
+for i in 69 67; do
+  (head -c 15767 tests/archive.zip; printf "\x$i"
+   tail -c +15769 tests/archive.zip) > tests/size$i.zip.new
+  for a in {0..1}; do
+    echo Testing -z -t -a$a: size$i
+    ./target/debug/rgzip -z -t -a$a tests/size$i.zip.new \
+      | grep "Deflate stream does not end at ZIP compressed size" \
+      > /dev/null || echo Failed
+  done
+done
+# Block 2 of bgzf.gz starts at compressed offset 7203 and byte 20000.
+tail -c +20101 tests/dynamic.old | head -c 25000 > tests/voffset.old
+for a in {0..1}; do
+  for b in {0..3}; do
+    echo Testing -o -b$b -a$a: bgzf
+    ./target/debug/rgzip -b$b -a$a -o $((7203 * 65536 + 100)) -n 25000 \
+      tests/bgzf.gz tests/voffset.new > /dev/null
+    diff -q tests/voffset.old tests/voffset.new
+  done
+done
+# A BC subfield of zero can't even hold the block header and trailer.
+echo Testing -o: bad BC subfield
+(head -c 16 tests/bgzf.gz; printf '\0\0'; tail -c +19 tests/bgzf.gz) \
+  > tests/badbc.gz.new
+./target/debug/rgzip -o 0 tests/badbc.gz.new tests/voffset.new \
+  | grep "Missing BGZF block size subfield" > /dev/null || echo Failed
+# The first block of bgzf.gz, with BSIZE one byte over and one byte under.
+for i in 23 21; do
+  (head -c 16 tests/bgzf.gz; printf "\x$i\x1c"; tail -c +19 tests/bgzf.gz) \
+    > tests/bsize$i.gz.new
+  for a in {0..1}; do
+    echo Testing -o -a$a: bsize$i
+    ./target/debug/rgzip -o 0 -a$a tests/bsize$i.gz.new tests/voffset.new \
+      | grep "Deflate stream does not end at BGZF block size" > /dev/null \
+      || echo Failed
+  done
+done
+for i in stored fixed dynamic; do
+  echo Testing -c: $i
+  ./target/debug/rgzip -c tests/$i.old tests/compress.gz.new > /dev/null
+  gzip -dc tests/compress.gz.new | diff -q tests/$i.old -
+  ./target/debug/rgzip tests/compress.gz.new tests/$i.new > /dev/null
+  diff -q tests/$i.old tests/$i.new
+done
+./target/debug/rgzip -v1 -c tests/dynamic.old tests/compress.gz.new \
+  | grep "Dynamic huffman block" > /dev/null || echo Failed
+# Three copies of dynamic.old take more than one BGZF block, dictzip chunk
+# or gzip window.
+cat tests/dynamic.old tests/dynamic.old tests/dynamic.old > tests/triple.old
+echo Testing -c: triple
+./target/debug/rgzip -c tests/triple.old tests/compress.gz.new > /dev/null
+gzip -dc tests/compress.gz.new | cmp - tests/triple.old
+echo Testing -B: triple
+./target/debug/rgzip -B tests/triple.old tests/bgzf.new > /dev/null
+[ $(stat -c %s tests/bgzf.new) -lt $(stat -c %s tests/triple.old) ] \
+  || echo Failed
+gzip -dc tests/bgzf.new | cmp - tests/triple.old
+./target/debug/rgzip tests/bgzf.new tests/triple.new > /dev/null
+diff -q tests/triple.old tests/triple.new
+# dictzip.dz has chunks of 10000 bytes, so this range spans three chunks.
+tail -c +9996 tests/dynamic.old | head -c 20010 > tests/range.old
+for a in {0..1}; do
+  for b in {0..3}; do
+    echo Testing -r -b$b -a$a: dictzip
+    ./target/debug/rgzip -b$b -a$a -r 9995 -n 20010 \
+      tests/dictzip.dz tests/range.new > /dev/null
+    diff -q tests/range.old tests/range.new
+  done
+done
+echo Testing -Z: triple
+./target/debug/rgzip -Z tests/triple.old tests/dictzip.new > /dev/null
+[ $(stat -c %s tests/dictzip.new) -lt $(stat -c %s tests/triple.old) ] \
+  || echo Failed
+gzip -dc tests/dictzip.new | cmp - tests/triple.old
+tail -c +100001 tests/triple.old | head -c 30000 > tests/range.old
+for a in {0..1}; do
+  for b in {0..3}; do
+    echo Testing -Z -r -b$b -a$a: triple
+    ./target/debug/rgzip -b$b -a$a -r 0 tests/dictzip.new tests/triple.new \
+      > /dev/null
+    diff -q tests/triple.old tests/triple.new
+    ./target/debug/rgzip -b$b -a$a -r 100000 -n 30000 \
+      tests/dictzip.new tests/range.new > /dev/null
+    diff -q tests/range.old tests/range.new
+  done
+done
+echo Testing trailer errors
+# Same stream as dynamic.gz, with a zeroed CRC32 or ISIZE in the trailer.
+(head -c -8 tests/dynamic.gz; printf '\0\0\0\0'
+ tail -c 4 tests/dynamic.gz) > tests/crc32.gz.new
+(head -c -4 tests/dynamic.gz; printf '\0\0\0\0') > tests/isize.gz.new
+for b in {0..3}; do
+  ./target/debug/rgzip -b$b tests/crc32.gz.new tests/dynamic.new \
+    | grep "CRC32 of decoded data does not match trailer" > /dev/null \
+    || echo Failed
+  ./target/debug/rgzip -b$b tests/isize.gz.new tests/dynamic.new \
+    | grep "Size of decoded data does not match trailer" > /dev/null \
+    || echo Failed
+done
+echo Testing header errors
+# Same stream as extra.gz, with XLEN one byte short of its two subfields.
+(head -c 10 tests/extra.gz; printf '\x0c'
+ tail -c +12 tests/extra.gz) > tests/xlen.gz.new
+./target/debug/rgzip tests/xlen.gz.new tests/extra.new \
+  | grep "Extra field lengths do not add up to XLEN" > /dev/null || echo Failed
+# Same stream as hcrc.gz, with a changed subfield id under the header CRC16.
+(head -c 13 tests/hcrc.gz; printf 'Z'; tail -c +15 tests/hcrc.gz) \
+  > tests/badhcrc.gz.new
+./target/debug/rgzip tests/badhcrc.gz.new tests/hcrc.new \
+  | grep "Header CRC16 mismatch" > /dev/null || echo Failed
+echo Testing -T: trailing data
+(cat tests/dynamic.gz; head -c 512 /dev/zero) > tests/padded.gz.new
+(cat tests/dynamic.gz; echo garbage) > tests/garbage.gz.new
+./target/debug/rgzip -T2 tests/padded.gz.new tests/dynamic.new > /dev/null
+diff -q tests/dynamic.old tests/dynamic.new
+./target/debug/rgzip -T1 tests/garbage.gz.new tests/dynamic.new > /dev/null
+diff -q tests/dynamic.old tests/dynamic.new
+./target/debug/rgzip -T2 tests/garbage.gz.new tests/dynamic.new \
+  | grep "Trailing data at offset 14714" > /dev/null || echo Failed
+# text.gz has the same CRLF data in a text member and in a binary member.
+(cat tests/fixed.old; sed 's/$/\r/' tests/fixed.old) > tests/ascii.old
+for b in {0..3}; do
+  echo Testing -A -b$b: text
+  ./target/debug/rgzip -A -b$b tests/text.gz tests/ascii.new > /dev/null
+  diff -q tests/ascii.old tests/ascii.new
+done
+echo Testing list mode
+./target/debug/rgzip -l tests/multi.gz > tests/list.new
+[ $(grep -c "^deflate " tests/list.new) -eq 3 ] || echo Failed
+grep "^deflate 3060d77a Apr 26 03:18 *0 *49 *112 *83.0% abacate.txt$" \
+  tests/list.new > /dev/null || echo Failed
+grep "^deflate dd832650 Jan 01 00:00 *49 *764 *32897 *97.7% $" \
+  tests/list.new > /dev/null || echo Failed
+grep "^deflate 3060d77a Apr 26 03:18 *813 *49 *112 *83.0% abacate.txt$" \
+  tests/list.new > /dev/null || echo Failed
+grep "^ *862 *33121 *97.6% (totals, 3 members)$" tests/list.new > /dev/null \
+  || echo Failed
+echo Testing -N: name and mtime
+rm -rf tests/restore.new
+mkdir tests/restore.new
+cp tests/fixed.old tests/named.txt.new
+touch -d @1500000000 tests/named.txt.new
+gzip -c tests/named.txt.new > tests/named.gz.new
+rm tests/named.txt.new
+./target/debug/rgzip -N tests/named.gz.new tests/restore.new > /dev/null
+diff -q tests/fixed.old tests/restore.new/named.txt.new
+[ "$(stat -c %Y tests/restore.new/named.txt.new)" = 1500000000 ] \
+  || echo Failed
+echo bogus > tests/restore.new/named.txt.new
+./target/debug/rgzip -N tests/named.gz.new tests/restore.new \
+  | grep "Output file already exists" > /dev/null || echo Failed
+[ "$(cat tests/restore.new/named.txt.new)" = bogus ] || echo Failed
+./target/debug/rgzip -N -F tests/named.gz.new tests/restore.new > /dev/null
+diff -q tests/fixed.old tests/restore.new/named.txt.new
+# Same stream as dynamic.gz, with a name that points outside the directory.
+(printf '\x1f\x8b\x08\x08\0\0\0\0\0\x03../evil\0'
+ tail -c +11 tests/dynamic.gz) > tests/evil.gz.new
+./target/debug/rgzip -N tests/evil.gz.new tests/restore.new \
+  | grep "Original filename is not a safe file name" > /dev/null || echo Failed
+[ ! -e tests/evil ] || echo Failed
+rm -rf tests/restore.new
+echo Testing probe mode
+./target/debug/rgzip -p tests/hcrc.gz | grep "Header size: 30" > /dev/null \
+  || echo Failed
+./target/debug/rgzip -p tests/multi.gz \
+  | grep "^Last member CRC32: 3060d77a, ISIZE: 112$" > /dev/null || echo Failed
+# A pipe can't seek, so only the header is shown.
+cat tests/hcrc.gz | ./target/debug/rgzip -p /dev/stdin > tests/probe.new
+grep "Header size: 30" tests/probe.new > /dev/null || echo Failed
+grep "CRC32" tests/probe.new > /dev/null && echo Failed
+echo Testing integrity mode
+./target/debug/rgzip -t tests/*.gz tests/zlib.zz tests/window.zz \
+  tests/dictzip.dz tests/*.Z tests/*.z tests/*.lzh > /dev/null || echo Failed
+./target/debug/rgzip -z -t tests/*.zip > /dev/null || echo Failed
+# Same stream as dynamic.gz, with one payload byte changed.
+(head -c 1000 tests/dynamic.gz; printf '\x55'
+ tail -c +1002 tests/dynamic.gz) > tests/flipped.gz.new
+head -c 5000 tests/dynamic.gz > tests/truncated.gz.new
+# A bad member between two good ones.
+cat tests/fixed.gz tests/crc32.gz.new tests/stored.gz > tests/members.gz.new
+for i in flipped crc32 truncated members; do
+  ./target/debug/rgzip -t tests/fixed.gz tests/$i.gz.new tests/stored.gz \
+    > tests/test.new && echo Failed
+  grep "^tests/$i.gz.new: Error: " tests/test.new > /dev/null || echo Failed
+  [ $(grep -c ": OK$" tests/test.new) -eq 2 ] || echo Failed
+done
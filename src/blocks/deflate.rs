@@ -0,0 +1,159 @@
+use errors::{GzipResult, GzipError};
+use sources::bitsource::BitSource;
+use sinks::bitsink::BitSink;
+use blocks::stored::{BlockStored, StoredEncoder, MAX_STORED_LENGTH};
+use blocks::fixed::{BlockFixed, FixedEncoder};
+use blocks::lz77::{Matcher, Token};
+use blocks::dynamic::{BlockDynamic, DynamicTables, DynamicEncoder};
+use blocks::window::DeflateFormat;
+use buffers::outputbuffer::OutputBuffer;
+use context::VERBOSE;
+
+const BLOCK_TOKENS : usize = 16384;
+
+// Stored blocks cost the 3 header bits, up to 7 bits of padding and the
+// 32 bits of LEN and NLEN, besides the data.
+const STORED_OVERHEAD : u64 = 42;
+
+#[allow(non_snake_case)]
+struct BlockHeader {
+    BFINAL: u8,
+    BTYPE: u8,
+}
+
+pub struct DeflateDecoder<'a> {
+    input: &'a mut BitSource,
+    output: &'a mut OutputBuffer,
+    format: DeflateFormat,
+}
+
+impl<'a> DeflateDecoder<'a> {
+    pub fn new(input: &'a mut BitSource, output: &'a mut OutputBuffer,
+               format: DeflateFormat) -> Self {
+        DeflateDecoder{ input, output, format }
+    }
+
+    pub fn decode(&mut self) -> GzipResult<()> {
+        for i in 1.. {
+            if self.decode_block(i)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Stops at the first block boundary at or past the byte offset end,
+    // which is where a full flush leaves the input.
+    pub fn decode_until(&mut self, end: u64) -> GzipResult<()> {
+        for i in 1.. {
+            if self.decode_block(i)? || self.input.position() >= end {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_block(&mut self, index: u32) -> GzipResult<bool> {
+        let header = BlockHeader{
+            BFINAL: self.input.get_bit()? as u8,
+            BTYPE: self.input.get_bits_rev(2)? as u8,
+        };
+        verbose!(1, "Block {} is final: {}", index, header.BFINAL > 0);
+        let bits = &mut *self.input;
+        let output = &mut *self.output;
+        try!(match header.BTYPE {
+            0 => BlockStored::new(bits, output).decode(),
+            1 => BlockFixed::new(bits, output, self.format).decode(),
+            2 => BlockDynamic::new(bits, output, self.format).decode(),
+            _ => Err(GzipError::DeflateModeNotSupported),
+        });
+        Ok(header.BFINAL > 0)
+    }
+}
+
+pub struct DeflateEncoder<'a> {
+    output: &'a mut BitSink
+}
+
+impl<'a> DeflateEncoder<'a> {
+    pub fn new(output: &'a mut BitSink) -> Self {
+        DeflateEncoder{ output }
+    }
+
+    pub fn encode(&mut self, data: &[u8]) -> GzipResult<()> {
+        self.encode_blocks(data, 0, true)
+    }
+
+    // Encodes data[history..] as the next part of a longer stream, with
+    // matches that can reach back into data[..history].
+    pub fn encode_part(&mut self, data: &[u8], history: usize, last: bool)
+        -> GzipResult<()> {
+
+        self.encode_blocks(data, history, last)
+    }
+
+    // Ends with an empty stored block instead of a final one, so the
+    // output is byte aligned and what follows doesn't refer to this data.
+    pub fn full_flush(&mut self, data: &[u8]) -> GzipResult<()> {
+        if !data.is_empty() {
+            self.encode_blocks(data, 0, false)?;
+        }
+        self.put_header(false, 0)?;
+        StoredEncoder::new(self.output).encode(&[])
+    }
+
+    fn encode_blocks(&mut self, data: &[u8], history: usize, last: bool)
+        -> GzipResult<()> {
+
+        let mut matcher = Matcher::with_history(data, history);
+        let mut start = history;
+        for i in 1.. {
+            let tokens : Vec<Token> =
+                matcher.by_ref().take(BLOCK_TOKENS).collect();
+            let end = matcher.position();
+            let done = end == data.len();
+            self.encode_block(i, &data[start..end], &tokens, last && done)?;
+            if done {
+                break;
+            }
+            start = end;
+        }
+        Ok(())
+    }
+
+    // Each block uses whichever of dynamic huffman, fixed huffman or as many
+    // stored blocks as needed for the same data is smaller.
+    fn encode_block(&mut self, index: u32, data: &[u8], tokens: &[Token],
+                    last: bool) -> GzipResult<()> {
+
+        verbose!(1, "Block {} is final: {}", index, last);
+        let tables = DynamicTables::new(tokens);
+        let dynamic = tables.cost(tokens);
+        let fixed = FixedEncoder::cost(tokens);
+        let chunks : Vec<&[u8]> = data.chunks(MAX_STORED_LENGTH).collect();
+        let stored = chunks.len() as u64 * STORED_OVERHEAD +
+            8 * data.len() as u64;
+        if !chunks.is_empty() && stored < fixed.min(dynamic) {
+            for (i, chunk) in chunks.iter().enumerate() {
+                self.put_header(last && i + 1 == chunks.len(), 0)?;
+                StoredEncoder::new(self.output).encode(chunk)?;
+            }
+            Ok(())
+        } else if dynamic < fixed {
+            self.put_header(last, 2)?;
+            DynamicEncoder::new(self.output, tables).encode(tokens)
+        } else {
+            self.put_header(last, 1)?;
+            FixedEncoder::new(self.output).encode(tokens)
+        }
+    }
+
+    fn put_header(&mut self, last: bool, btype: u8) -> GzipResult<()> {
+        let header = BlockHeader{
+            BFINAL: if last { 1 } else { 0 },
+            BTYPE: btype
+        };
+        self.output.put_bit(header.BFINAL as u32)?;
+        self.output.put_bits_rev(header.BTYPE as u32, 2)
+    }
+}
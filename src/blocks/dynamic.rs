@@ -1,8 +1,11 @@
 use errors::{GzipResult, GzipError};
 use sources::bitsource::BitSource;
-use OutputBuffer;
+use sinks::bitsink::BitSink;
+use buffers::outputbuffer::OutputBuffer;
 use blocks::huffman::Huffman;
-use blocks::window::{WindowDecoder, BlockWindow};
+use blocks::lz77::Token;
+use blocks::window::{WindowDecoder, WindowEncoder, BlockWindow, DeflateFormat};
+use blocks::window::{window_cost, length_symbol, distance_symbol};
 use context::VERBOSE;
 
 #[allow(non_snake_case)]
@@ -15,6 +18,7 @@ struct DynamicHeader {
 pub struct BlockDynamic<'a> {
     input: &'a mut BitSource,
     output: &'a mut OutputBuffer,
+    format: DeflateFormat,
     literals: Huffman,
     distances: Huffman
 }
@@ -22,6 +26,7 @@ pub struct BlockDynamic<'a> {
 pub struct BlockDynamicBuilder<'a> {
     input: &'a mut BitSource,
     output: &'a mut OutputBuffer,
+    format: DeflateFormat,
 }
 
 impl<'a> BlockWindow for BlockDynamic<'a> {
@@ -32,15 +37,27 @@ impl<'a> BlockWindow for BlockDynamic<'a> {
     fn get_output(&mut self) -> &mut OutputBuffer {
         self.output
     }
+
+    fn get_format(&self) -> DeflateFormat {
+        self.format
+    }
 }
 
 const CODE_LENGTHS_UNSHUFFLE : [usize; 19] =
     [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
 
+const LITERAL_CODES : usize = 286;
+const DISTANCE_CODES : usize = 30;
+const MAX_CODE_LENGTH : u8 = 15;
+const MAX_LENGTHS_CODE_LENGTH : u8 = 7;
+
+// A code length symbol with the value and size of its extra bits.
+type LengthSymbol = (usize, u32, u8);
+
 impl<'a> BlockDynamic<'a> {
-    pub fn new(input: &'a mut BitSource, output: &'a mut OutputBuffer)
-            -> BlockDynamicBuilder<'a> {
-        BlockDynamicBuilder{ input, output }
+    pub fn new(input: &'a mut BitSource, output: &'a mut OutputBuffer,
+               format: DeflateFormat) -> BlockDynamicBuilder<'a> {
+        BlockDynamicBuilder{ input, output, format }
     }
 }
 
@@ -69,6 +86,7 @@ impl<'a> BlockDynamicBuilder<'a> {
         let mut decoder = BlockDynamic {
             input: self.input,
             output: self.output,
+            format: self.format,
             literals,
             distances };
         decoder.window_decode()
@@ -126,3 +144,155 @@ impl<'a> WindowDecoder for BlockDynamic<'a> {
     }
 }
 
+pub struct DynamicTables {
+    header: DynamicHeader,
+    literals: Vec<u8>,
+    distances: Vec<u8>,
+    code_lengths: Vec<u8>,
+    symbols: Vec<LengthSymbol>
+}
+
+pub struct DynamicEncoder<'a> {
+    output: &'a mut BitSink,
+    tables: DynamicTables,
+    literals: Vec<u32>,
+    distances: Vec<u32>
+}
+
+fn used_codes(lengths: &[u8]) -> usize {
+    lengths.iter().rposition(|&length| length > 0).map_or(0, |i| i + 1)
+}
+
+impl DynamicTables {
+    pub fn new(tokens: &[Token]) -> Self {
+        let mut literal_count = vec![0; LITERAL_CODES];
+        let mut distance_count = vec![0; DISTANCE_CODES];
+        literal_count[256] = 1;
+        for token in tokens {
+            match *token {
+                Token::Literal(byte) => literal_count[byte as usize] += 1,
+                Token::Window{ length, distance } => {
+                    literal_count[length_symbol(length).0 as usize] += 1;
+                    distance_count[distance_symbol(distance).0 as usize] += 1;
+                }
+            }
+        }
+        let literals = Huffman::lengths(&literal_count, MAX_CODE_LENGTH);
+        let distances = Huffman::lengths(&distance_count, MAX_CODE_LENGTH);
+        let hlit = used_codes(&literals).max(257);
+        let hdist = used_codes(&distances).max(1);
+        let mut lengths = literals[..hlit].to_vec();
+        lengths.extend(&distances[..hdist]);
+        let symbols = Self::run_lengths(&lengths);
+        let mut symbol_count = vec![0; CODE_LENGTHS_UNSHUFFLE.len()];
+        for &(symbol, _, _) in &symbols {
+            symbol_count[symbol] += 1;
+        }
+        let code_lengths =
+            Huffman::lengths(&symbol_count, MAX_LENGTHS_CODE_LENGTH);
+        let shuffled : Vec<u8> = CODE_LENGTHS_UNSHUFFLE.iter()
+            .map(|&pos| code_lengths[pos])
+            .collect();
+        let header = DynamicHeader {
+            HLIT: hlit as u16,
+            HDIST: hdist as u16,
+            HCLEN: used_codes(&shuffled).max(4) as u16
+        };
+        DynamicTables { header, literals, distances, code_lengths, symbols }
+    }
+
+    // The inverse of decode_lengths: runs of zeros use 17 or 18, runs of
+    // any other length are sent once and then repeated with 16.
+    fn run_lengths(lengths: &[u8]) -> Vec<LengthSymbol> {
+        let mut symbols = vec![];
+        let mut i = 0;
+        while i < lengths.len() {
+            let length = lengths[i];
+            let run = lengths[i..].iter()
+                .take_while(|&&x| x == length)
+                .count();
+            if length == 0 && run >= 11 {
+                let size = run.min(138);
+                symbols.push((18, size as u32 - 11, 7));
+                i += size;
+            } else if length == 0 && run >= 3 {
+                let size = run.min(10);
+                symbols.push((17, size as u32 - 3, 3));
+                i += size;
+            } else {
+                symbols.push((length as usize, 0, 0));
+                i += 1;
+                let mut rest = if length > 0 { run - 1 } else { 0 };
+                while rest >= 3 {
+                    let repeat = rest.min(6);
+                    symbols.push((16, repeat as u32 - 3, 2));
+                    rest -= repeat;
+                    i += repeat;
+                }
+            }
+        }
+        symbols
+    }
+
+    // Size in bits of the block for these tokens, including the header.
+    pub fn cost(&self, tokens: &[Token]) -> u64 {
+        let lengths : u64 = self.symbols.iter()
+            .map(|&(symbol, _, extra)|
+                 self.code_lengths[symbol] as u64 + extra as u64)
+            .sum();
+        3 + 14 + 3 * self.header.HCLEN as u64 + lengths +
+            window_cost(tokens,
+                        |code| self.literals[code as usize] as u64,
+                        |code| self.distances[code as usize] as u64)
+    }
+}
+
+impl<'a> DynamicEncoder<'a> {
+    pub fn new(output: &'a mut BitSink, tables: DynamicTables) -> Self {
+        let literals = Huffman::codes(&tables.literals);
+        let distances = Huffman::codes(&tables.distances);
+        DynamicEncoder{ output, tables, literals, distances }
+    }
+
+    pub fn encode(&mut self, tokens: &[Token]) -> GzipResult<()> {
+        let header = &self.tables.header;
+        verbose!(1, "Dynamic huffman block, HLIT {}, HDIST {}, HCLEN {}",
+                 header.HLIT, header.HDIST, header.HCLEN);
+        self.output.put_bits_rev(header.HLIT as u32 - 257, 5)?;
+        self.output.put_bits_rev(header.HDIST as u32 - 1, 5)?;
+        self.output.put_bits_rev(header.HCLEN as u32 - 4, 4)?;
+        let code_lengths = &self.tables.code_lengths;
+        for i in 0..header.HCLEN {
+            let pos = CODE_LENGTHS_UNSHUFFLE[i as usize];
+            self.output.put_bits_rev(code_lengths[pos] as u32, 3)?;
+        }
+        let codes = Huffman::codes(code_lengths);
+        for &(symbol, value, extra) in &self.tables.symbols {
+            self.output.put_bits(codes[symbol], code_lengths[symbol])?;
+            self.output.put_bits_rev(value, extra)?;
+        }
+        self.window_encode(tokens)
+    }
+}
+
+impl<'a> WindowEncoder for DynamicEncoder<'a> {
+    fn get_sink(&mut self) -> &mut BitSink {
+        self.output
+    }
+
+    fn put_literal(&mut self, code: u32) -> GzipResult<()> {
+        let length = self.tables.literals[code as usize];
+        if length == 0 {
+            return Err(GzipError::InternalError);
+        }
+        self.output.put_bits(self.literals[code as usize], length)
+    }
+
+    fn put_distance(&mut self, code: u32) -> GzipResult<()> {
+        let length = self.tables.distances[code as usize];
+        if length == 0 {
+            return Err(GzipError::InternalError);
+        }
+        self.output.put_bits(self.distances[code as usize], length)
+    }
+}
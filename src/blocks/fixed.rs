@@ -1,17 +1,22 @@
 use errors::{GzipResult, GzipError};
 use sources::bitsource::BitSource;
-use blocks::window::{WindowDecoder, BlockWindow};
-use OutputBuffer;
+use sinks::bitsink::BitSink;
+use blocks::lz77::Token;
+use blocks::window::{WindowDecoder, WindowEncoder, BlockWindow, DeflateFormat};
+use blocks::window::window_cost;
+use buffers::outputbuffer::OutputBuffer;
 use context::VERBOSE;
 
 pub struct BlockFixed<'a> {
     input: &'a mut BitSource,
     output: &'a mut OutputBuffer,
+    format: DeflateFormat,
 }
 
 impl<'a> BlockFixed<'a> {
-    pub fn new(input: &'a mut BitSource, output: &'a mut OutputBuffer) -> Self {
-        BlockFixed{ input, output }
+    pub fn new(input: &'a mut BitSource, output: &'a mut OutputBuffer,
+               format: DeflateFormat) -> Self {
+        BlockFixed{ input, output, format }
     }
 
     pub fn decode(&mut self) -> GzipResult<()> {
@@ -28,6 +33,10 @@ impl<'a> BlockWindow for BlockFixed<'a> {
     fn get_output(&mut self) -> &mut OutputBuffer {
         self.output
     }
+
+    fn get_format(&self) -> DeflateFormat {
+        self.format
+    }
 }
 
 impl<'a> WindowDecoder for BlockFixed<'a> {
@@ -39,7 +48,7 @@ impl<'a> WindowDecoder for BlockFixed<'a> {
             0x18...0x5F =>
                 Ok((base << 1) + self.input.get_bits(1)? - 0x30),
             0x60...0x63 =>
-                Ok((base << 1) + self.input.get_bits(1)? - 0xC0 + 256),
+                Ok((base << 1) + self.input.get_bits(1)? - 0xC0 + 280),
             0x64...0x7F =>
                 Ok((base << 2) + self.input.get_bits(2)? - 0x190 + 144),
             _ => Err(GzipError::InternalError)
@@ -51,4 +60,51 @@ impl<'a> WindowDecoder for BlockFixed<'a> {
     }
 }
 
+pub struct FixedEncoder<'a> {
+    output: &'a mut BitSink
+}
+
+impl<'a> FixedEncoder<'a> {
+    pub fn new(output: &'a mut BitSink) -> Self {
+        FixedEncoder{ output }
+    }
+
+    pub fn encode(&mut self, tokens: &[Token]) -> GzipResult<()> {
+        verbose!(1, "Fixed huffman block");
+        self.window_encode(tokens)
+    }
+
+    // Size in bits of the block for these tokens, including the header.
+    pub fn cost(tokens: &[Token]) -> u64 {
+        3 + window_cost(tokens, Self::literal_size, |_| 5)
+    }
 
+    fn literal_size(code: u32) -> u64 {
+        match code {
+            0...143 => 8,
+            144...255 => 9,
+            256...279 => 7,
+            _ => 8
+        }
+    }
+}
+
+impl<'a> WindowEncoder for FixedEncoder<'a> {
+    fn get_sink(&mut self) -> &mut BitSink {
+        self.output
+    }
+
+    fn put_literal(&mut self, code: u32) -> GzipResult<()> {
+        match code {
+            0...143 => self.output.put_bits(code + 0x30, 8),
+            144...255 => self.output.put_bits(code - 144 + 0x190, 9),
+            256...279 => self.output.put_bits(code - 256, 7),
+            280...287 => self.output.put_bits(code - 280 + 0xC0, 8),
+            _ => Err(GzipError::InternalError)
+        }
+    }
+
+    fn put_distance(&mut self, code: u32) -> GzipResult<()> {
+        self.output.put_bits(code, 5)
+    }
+}
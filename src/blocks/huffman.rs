@@ -14,9 +14,23 @@ type HuffmanSlice = [(u8, u16, u32)];
 
 impl HuffmanNode {
     pub fn build(codes : Vec<u8>) -> GzipResult<Self> {
-        let max = (1 + codes.iter().max().unwrap()) as usize;
+        let canonical = Self::codes(&codes);
+        let mut huffman : HuffmanCode = codes.iter()
+            .zip(canonical)
+            .enumerate()
+            .filter(|&(_, (bits, _))| *bits > 0)
+            .map(|(code, (bits, value))|
+                 (*bits, code as u16, Self::reverse_bits(value, *bits)))
+            .collect();
+        huffman.sort();
+        Self::build_trie(&huffman, 0, huffman.len() - 1, 0)
+    }
+
+    // Canonical codes for the given lengths, as in RFC 1951 3.2.2.
+    pub fn codes(lengths: &[u8]) -> Vec<u32> {
+        let max = (1 + lengths.iter().max().unwrap()) as usize;
         let mut bit_count = vec![0; max];
-        for i in &codes {
+        for i in lengths {
             bit_count[*i as usize] += 1;
         }
         let mut next_count = vec![0; max];
@@ -25,17 +39,52 @@ impl HuffmanNode {
             next_count[i] = prev;
             prev = (prev + bit_count[i]) << 1;
         }
-        let mut huffman : HuffmanCode = vec![];
-        let valid_codes = codes.iter().enumerate().filter(|&(_, x)| *x > 0);
+        let mut codes = vec![0; lengths.len()];
+        let valid_codes = lengths.iter().enumerate().filter(|&(_, x)| *x > 0);
         for (code, bits) in valid_codes {
-            let triple =
-                (*bits, code as u16,
-                 Self::reverse_bits(next_count[*bits as usize], *bits));
-            huffman.push(triple);
+            codes[code] = next_count[*bits as usize];
             next_count[*bits as usize] += 1;
         }
-        huffman.sort();
-        Self::build_trie(&huffman, 0, huffman.len() - 1, 0)
+        codes
+    }
+
+    // Package-merge: lengths of an optimal prefix code with no code longer
+    // than limit. Unused symbols get length zero, except that there are
+    // always two codes, since a single one would be decoded reading no bits.
+    pub fn lengths(frequencies: &[u32], limit: u8) -> Vec<u8> {
+        let mut leaves : Vec<(u64, Vec<u16>)> = frequencies.iter()
+            .enumerate()
+            .filter(|&(_, frequency)| *frequency > 0)
+            .map(|(symbol, frequency)|
+                 (*frequency as u64, vec![symbol as u16]))
+            .collect();
+        for (symbol, frequency) in frequencies.iter().enumerate() {
+            if leaves.len() >= 2 {
+                break;
+            }
+            if *frequency == 0 {
+                leaves.push((1, vec![symbol as u16]));
+            }
+        }
+        leaves.sort();
+        let mut list = leaves.clone();
+        for _ in 1..limit {
+            let mut merged = leaves.clone();
+            for pair in list.chunks(2).filter(|pair| pair.len() == 2) {
+                let mut symbols = pair[0].1.clone();
+                symbols.extend(&pair[1].1);
+                merged.push((pair[0].0 + pair[1].0, symbols));
+            }
+            merged.sort_by_key(|&(weight, _)| weight);
+            list = merged;
+        }
+        let mut lengths = vec![0; frequencies.len()];
+        for (_, symbols) in list.iter().take(2 * leaves.len() - 2) {
+            for symbol in symbols {
+                lengths[*symbol as usize] += 1;
+            }
+        }
+        lengths
     }
 
    fn reverse_bits(value : u32, bits: u8) -> u32 {
@@ -101,3 +150,17 @@ impl HuffmanNode {
         }
     }
 }
+
+#[test]
+fn lengths_are_limited_and_complete() {
+    let mut frequencies = vec![1, 1];
+    for i in 2..30 {
+        let next = frequencies[i - 1] + frequencies[i - 2];
+        frequencies.push(next);
+    }
+    let lengths = HuffmanNode::lengths(&frequencies, 15);
+    assert!(*lengths.iter().max().unwrap() == 15);
+    let kraft : u32 = lengths.iter().map(|&x| 1 << (15 - x)).sum();
+    assert!(kraft == 1 << 15);
+    assert!(HuffmanNode::lengths(&[0, 0, 7], 15) == vec![1, 0, 1]);
+}
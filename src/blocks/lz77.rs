@@ -0,0 +1,144 @@
+const WINDOW_SIZE : usize = 32768;
+const WINDOW_MASK : usize = WINDOW_SIZE - 1;
+const HASH_BITS : usize = 15;
+const HASH_MASK : usize = (1 << HASH_BITS) - 1;
+const MIN_MATCH : usize = 3;
+const MAX_MATCH : usize = 258;
+const MAX_CHAIN : usize = 128;
+const NIL : usize = usize::MAX;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Token {
+    Literal(u8),
+    Window{length: u32, distance: u32}
+}
+
+// Greedy LZ77 over a 32K window. Every position is chained by the hash of
+// its next three bytes, and prev is indexed modulo the window, so an entry
+// that does not point backwards has been overwritten and ends the chain.
+pub struct Matcher<'a> {
+    data: &'a [u8],
+    pos: usize,
+    head: Vec<usize>,
+    prev: Vec<usize>
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Matcher {
+            data,
+            pos: 0,
+            head: vec![NIL; 1 << HASH_BITS],
+            prev: vec![NIL; WINDOW_SIZE]
+        }
+    }
+
+    // Matches may refer back into data[..history], but only data[history..]
+    // is turned into tokens.
+    pub fn with_history(data: &'a [u8], history: usize) -> Self {
+        let mut matcher = Matcher::new(data);
+        for pos in 0..history {
+            matcher.insert(pos);
+        }
+        matcher.pos = history;
+        matcher
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn hash(&self, pos: usize) -> usize {
+        let data = &self.data[pos..pos + MIN_MATCH];
+        ((data[0] as usize) << 10 ^ (data[1] as usize) << 5 ^
+         data[2] as usize) & HASH_MASK
+    }
+
+    fn insert(&mut self, pos: usize) {
+        if pos + MIN_MATCH <= self.data.len() {
+            let hash = self.hash(pos);
+            self.prev[pos & WINDOW_MASK] = self.head[hash];
+            self.head[hash] = pos;
+        }
+    }
+
+    fn longest_match(&self) -> (usize, usize) {
+        if self.pos + MIN_MATCH > self.data.len() {
+            return (0, 0);
+        }
+        let limit = MAX_MATCH.min(self.data.len() - self.pos);
+        let current = &self.data[self.pos..self.pos + limit];
+        let mut best = (0, 0);
+        let mut candidate = self.head[self.hash(self.pos)];
+        for _ in 0..MAX_CHAIN {
+            if candidate >= self.pos || self.pos - candidate > WINDOW_SIZE {
+                break;
+            }
+            let length = current.iter()
+                .zip(&self.data[candidate..])
+                .take_while(|&(a, b)| a == b)
+                .count();
+            if length > best.0 {
+                best = (length, self.pos - candidate);
+                if length == limit {
+                    break;
+                }
+            }
+            let next = self.prev[candidate & WINDOW_MASK];
+            if next >= candidate {
+                break;
+            }
+            candidate = next;
+        }
+        best
+    }
+}
+
+impl<'a> Iterator for Matcher<'a> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        if self.pos >= self.data.len() {
+            return None;
+        }
+        let (length, distance) = self.longest_match();
+        let (token, advance) = if length >= MIN_MATCH {
+            (Token::Window{ length: length as u32, distance: distance as u32 },
+             length)
+        } else {
+            (Token::Literal(self.data[self.pos]), 1)
+        };
+        for _ in 0..advance {
+            let pos = self.pos;
+            self.insert(pos);
+            self.pos += 1;
+        }
+        Some(token)
+    }
+}
+
+#[test]
+fn tokens_rebuild_the_input() {
+    let data = b"abcabcabcabcxyzxyabcabcaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    let mut output : Vec<u8> = vec![];
+    for token in Matcher::new(data) {
+        match token {
+            Token::Literal(byte) => output.push(byte),
+            Token::Window{ length, distance } => {
+                assert!(length as usize >= MIN_MATCH);
+                for _ in 0..length {
+                    let byte = output[output.len() - distance as usize];
+                    output.push(byte);
+                }
+            }
+        }
+    }
+    assert!(output == data.to_vec());
+}
+
+#[test]
+fn tokens_can_refer_to_history() {
+    let data = b"abcdefghijabcdefghij";
+    let tokens : Vec<Token> = Matcher::with_history(data, 10).collect();
+    assert!(tokens == vec![Token::Window{ length: 10, distance: 10 }]);
+}
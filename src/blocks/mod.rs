@@ -3,3 +3,5 @@ pub mod fixed;
 pub mod dynamic;
 pub mod huffman;
 pub mod window;
+pub mod deflate;
+pub mod lz77;
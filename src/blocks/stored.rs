@@ -1,8 +1,11 @@
 use errors::{GzipResult, GzipError};
 use sources::bitsource::BitSource;
-use OutputBuffer;
+use sinks::bitsink::BitSink;
+use buffers::outputbuffer::OutputBuffer;
 use context::VERBOSE;
 
+pub const MAX_STORED_LENGTH : usize = 65535;
+
 #[allow(non_snake_case)]
 struct StoredHeader {
     LEN: u16,
@@ -36,4 +39,27 @@ impl<'a> BlockStored<'a> {
     }
 }
 
+pub struct StoredEncoder<'a> {
+    output: &'a mut BitSink
+}
+
+impl<'a> StoredEncoder<'a> {
+    pub fn new(output: &'a mut BitSink) -> Self {
+        StoredEncoder{ output }
+    }
 
+    pub fn encode(&mut self, data: &[u8]) -> GzipResult<()> {
+        if data.len() > MAX_STORED_LENGTH {
+            return Err(GzipError::InternalError);
+        }
+        let header = StoredHeader{
+            LEN: data.len() as u16,
+            NLEN: !(data.len() as u16)
+        };
+        self.output.put_data(&header.LEN.to_le_bytes())?;
+        self.output.put_data(&header.NLEN.to_le_bytes())?;
+        self.output.put_data(data)?;
+        verbose!(1, "Stored block, len = {}", header.LEN);
+        Ok(())
+    }
+}
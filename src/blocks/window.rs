@@ -1,6 +1,8 @@
 use sources::bitsource::BitSource;
+use sinks::bitsink::BitSink;
+use blocks::lz77::Token;
 use errors::{GzipResult, GzipError};
-use OutputBuffer;
+use buffers::outputbuffer::OutputBuffer;
 use context::VERBOSE;
 
 const LENGTH_EXTRA : [u8; 29] =
@@ -32,7 +34,12 @@ fn ensure_lengths_are_consistent() {
     }
 }
 
-const DISTANCE_EXTRA : [u8; 30] =
+// Deflate64 redefines the last length code as 3 + 16 extra bits.
+const LENGTH64_EXTRA : u8 = 16;
+const LENGTH64_START : u32 = 3;
+
+// The last two distance codes are only valid in Deflate64.
+const DISTANCE_EXTRA : [u8; 32] =
     [0, 0, 0, 0,
      1, 1, 2, 2,
      3, 3, 4, 4,
@@ -40,9 +47,9 @@ const DISTANCE_EXTRA : [u8; 30] =
      7, 7, 8, 8,
      9, 9, 10, 10,
      11, 11, 12, 12,
-     13, 13];
+     13, 13, 14, 14];
 
-const DISTANCE_START : [u32; 30] =
+const DISTANCE_START : [u32; 32] =
     [1, 2, 3, 4,
      5, 7, 9, 13,
      17, 25, 33, 49,
@@ -50,7 +57,7 @@ const DISTANCE_START : [u32; 30] =
      257, 385, 513, 769,
      1025, 1537, 2049, 3073,
      4097, 6145, 8193, 12289,
-     16385, 24577];
+     16385, 24577, 32769, 49153];
 
 #[test]
 fn ensure_distances_are_consistent() {
@@ -59,11 +66,47 @@ fn ensure_distances_are_consistent() {
             DISTANCE_START[i + 1] ==
                 DISTANCE_START[i] + (1 << DISTANCE_EXTRA[i]));
     }
+    let last = DISTANCE_EXTRA.len() - 1;
+    assert!(DISTANCE_START[last] + (1 << DISTANCE_EXTRA[last]) - 1 ==
+            DeflateFormat::Deflate64.window_size() as u32);
+    assert!(DISTANCE_START[last - 2] + (1 << DISTANCE_EXTRA[last - 2]) - 1 ==
+            DeflateFormat::Deflate.window_size() as u32);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeflateFormat {
+    Deflate,
+    Deflate64
+}
+
+impl DeflateFormat {
+    pub fn window_size(&self) -> usize {
+        match *self {
+            DeflateFormat::Deflate => 32768,
+            DeflateFormat::Deflate64 => 65536
+        }
+    }
+
+    fn length_code(&self, index: usize) -> (u32, u8) {
+        match *self {
+            DeflateFormat::Deflate64 if index == LENGTH_START.len() - 1 =>
+                (LENGTH64_START, LENGTH64_EXTRA),
+            _ => (LENGTH_START[index], LENGTH_EXTRA[index])
+        }
+    }
+
+    fn distance_codes(&self) -> u32 {
+        match *self {
+            DeflateFormat::Deflate => 30,
+            DeflateFormat::Deflate64 => 32
+        }
+    }
 }
 
 pub trait BlockWindow {
     fn get_input(&mut self) -> &mut BitSource;
     fn get_output(&mut self) -> &mut OutputBuffer;
+    fn get_format(&self) -> DeflateFormat;
 }
 
 pub trait WindowDecoder : BlockWindow {
@@ -94,12 +137,11 @@ pub trait WindowDecoder : BlockWindow {
     }
 
     fn get_window(&mut self, length_base: u32) -> GzipResult<(u32, u32)> {
-        let index = length_base as usize - 257;
-        let length =
-            LENGTH_START[index] +
-            self.get_input().get_bits_rev(LENGTH_EXTRA[index])?;
+        let (start, extra) = self.get_format()
+            .length_code(length_base as usize - 257);
+        let length = start + self.get_input().get_bits_rev(extra)?;
         let distance_base = self.get_distance()?;
-        if distance_base >= 30 {
+        if distance_base >= self.get_format().distance_codes() {
             return Err(GzipError::InvalidDeflateStream);
         }
         let index = distance_base as usize;
@@ -109,3 +151,77 @@ pub trait WindowDecoder : BlockWindow {
         Ok((length, distance))
     }
 }
+
+// Returns the code for a match length or distance, with the value and size
+// of its extra bits. Length 258 has its own code, not 284 plus 31.
+pub fn length_symbol(length: u32) -> (u32, u32, u8) {
+    let index = LENGTH_START.iter().rposition(|&start| start <= length)
+        .unwrap_or(0);
+    (257 + index as u32, length - LENGTH_START[index], LENGTH_EXTRA[index])
+}
+
+pub fn distance_symbol(distance: u32) -> (u32, u32, u8) {
+    let codes = DeflateFormat::Deflate.distance_codes() as usize;
+    let index = DISTANCE_START[..codes].iter()
+        .rposition(|&start| start <= distance)
+        .unwrap_or(0);
+    (index as u32, distance - DISTANCE_START[index], DISTANCE_EXTRA[index])
+}
+
+// Size in bits of the tokens plus the end of block, given the size of each
+// literal and distance code.
+pub fn window_cost<L, D>(tokens: &[Token], literal: L, distance: D) -> u64
+    where L: Fn(u32) -> u64, D: Fn(u32) -> u64 {
+
+    let mut bits = literal(256);
+    for token in tokens {
+        bits += match *token {
+            Token::Literal(byte) => literal(byte as u32),
+            Token::Window{ length, distance: offset } => {
+                let (code, _, extra) = length_symbol(length);
+                let (distance_code, _, distance_extra) =
+                    distance_symbol(offset);
+                literal(code) + extra as u64 +
+                    distance(distance_code) + distance_extra as u64
+            }
+        };
+    }
+    bits
+}
+
+pub trait WindowEncoder {
+    fn get_sink(&mut self) -> &mut BitSink;
+
+    fn put_literal(&mut self, code: u32) -> GzipResult<()>;
+
+    fn put_distance(&mut self, code: u32) -> GzipResult<()>;
+
+    fn window_encode(&mut self, tokens: &[Token]) -> GzipResult<()> {
+        for token in tokens {
+            match *token {
+                Token::Literal(byte) => self.put_literal(byte as u32)?,
+                Token::Window{ length, distance } =>
+                    self.put_window(length, distance)?
+            }
+        }
+        self.put_literal(256)
+    }
+
+    fn put_window(&mut self, length: u32, distance: u32) -> GzipResult<()> {
+        let (code, value, extra) = length_symbol(length);
+        self.put_literal(code)?;
+        self.get_sink().put_bits_rev(value, extra)?;
+        let (code, value, extra) = distance_symbol(distance);
+        self.put_distance(code)?;
+        self.get_sink().put_bits_rev(value, extra)
+    }
+}
+
+#[test]
+fn symbols_match_the_tables() {
+    assert!(length_symbol(3) == (257, 0, 0));
+    assert!(length_symbol(257) == (284, 30, 5));
+    assert!(length_symbol(258) == (285, 0, 0));
+    assert!(distance_symbol(1) == (0, 0, 0));
+    assert!(distance_symbol(32768) == (29, 8191, 13));
+}
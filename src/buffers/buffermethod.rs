@@ -0,0 +1,29 @@
+use errors::GzipResult;
+use sinks::bytesink::ByteSinkProvider;
+use buffers::outputbuffer::OutputBuffer;
+use buffers::inmemory::InMemoryBuffer;
+use buffers::circular::CircularBuffer;
+use buffers::channel::ChannelBuffer;
+use buffers::copy::CopyBuffer;
+
+// Which OutputBuffer the readers that build their own create for a sink.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BufferMethod {
+    InMemory,
+    Circular,
+    Channel,
+    Copy
+}
+
+impl BufferMethod {
+    pub fn create(self, sink: ByteSinkProvider)
+        -> GzipResult<Box<OutputBuffer>> {
+
+        match self {
+            BufferMethod::InMemory => Ok(Box::new(InMemoryBuffer::new(sink)?)),
+            BufferMethod::Circular => Ok(Box::new(CircularBuffer::new(sink)?)),
+            BufferMethod::Channel => Ok(Box::new(ChannelBuffer::new(sink)?)),
+            BufferMethod::Copy => Ok(Box::new(CopyBuffer::new(sink)?))
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use errors::{GzipResult, GzipError};
 use buffers::outputbuffer::OutputBuffer;
 use sinks::bytesink::{ByteSink, ByteSinkProvider};
+use sinks::checksumsink::{ChecksumSink, StreamDigest};
+use checksums::checksum::Checksum;
+use checksums::crc32::Crc32;
 use context::VERBOSE;
 use std::thread;
 use std::thread::JoinHandle;
@@ -10,6 +13,10 @@ enum ChannelData {
     Value(u8),
     Vector(Vec<u8>),
     Window{length: u32, distance: u32},
+    Reset(Box<Checksum>, usize),
+    Prime(Vec<u8>),
+    Text(bool),
+    Digest(Sender<StreamDigest>),
     Exit
 }
 
@@ -17,11 +24,14 @@ struct ReceiverBuffer {
     buffer: Vec<u8>,
     pos: usize,
     size: usize,
-    output: Box<ByteSink>,
+    mask: usize,
+    output: ChecksumSink,
     rx: Receiver<ChannelData>
 }
 
 pub struct ChannelBuffer {
+    size: usize,
+    window: usize,
     tx: Sender<ChannelData>,
     handle: Option<JoinHandle<()>>
 }
@@ -35,6 +45,15 @@ fn forward_data(mut rb: ReceiverBuffer) -> GzipResult<()> {
             Ok(ChannelData::Window{length, distance}) => {
                 rb.copy_window(distance, length)?;
             },
+            Ok(ChannelData::Reset(checksum, window)) => {
+                rb.reset(checksum, window);
+            },
+            Ok(ChannelData::Prime(v)) => { rb.prime(v); },
+            Ok(ChannelData::Text(text)) => { rb.output.set_text(text)?; },
+            Ok(ChannelData::Digest(reply)) => {
+                reply.send(rb.output.digest())
+                    .or(Err(GzipError::InternalError))?;
+            },
             Ok(ChannelData::Exit) => { return Ok(()); },
             _ => panic!("Error in thread receiver")
         }
@@ -45,14 +64,15 @@ impl ChannelBuffer {
     pub fn new(provider: ByteSinkProvider) -> GzipResult<Self> {
         let (tx, rx) = channel();
         let handle = thread::spawn(move || {
-            let output = provider().unwrap();
+            let output = ChecksumSink::new(
+                provider().unwrap(), Box::new(Crc32::new()));
             let rb = ReceiverBuffer {
-                buffer: vec![0; 32768], pos: 0, size: 0,
+                buffer: vec![0; 32768], pos: 0, size: 0, mask: 32767,
                 output, rx
             };
             forward_data(rb).unwrap();
         });
-        Ok(ChannelBuffer{ tx, handle: Some(handle) })
+        Ok(ChannelBuffer{ size: 0, window: 32768, tx, handle: Some(handle) })
     }
 }
 
@@ -65,28 +85,66 @@ impl Drop for ChannelBuffer {
 
 impl OutputBuffer for ChannelBuffer {
     fn put_u8(&mut self, data: u8) -> GzipResult<()> {
+        self.size += 1;
         self.tx
             .send(ChannelData::Value(data))
             .or(Err(GzipError::InternalError))
     }
 
     fn put_data(&mut self, data: Vec<u8>) -> GzipResult<()> {
+        self.size += data.len();
         self.tx
             .send(ChannelData::Vector(data))
             .or(Err(GzipError::InternalError))
     }
 
     fn copy_window(&mut self, distance: u32, length: u32) -> GzipResult<()> {
+        // Checked here too, the receiver thread can't return the error.
+        if distance as usize > self.size || distance as usize > self.window {
+            return Err(GzipError::InvalidDeflateStream);
+        }
+        self.size += length as usize;
         self.tx
             .send(ChannelData::Window{length, distance})
             .or(Err(GzipError::InternalError))
     }
+
+    fn reset(&mut self, checksum: Box<Checksum>, window: usize)
+        -> GzipResult<()> {
+
+        self.size = 0;
+        self.window = window;
+        self.tx
+            .send(ChannelData::Reset(checksum, window))
+            .or(Err(GzipError::InternalError))
+    }
+
+    fn prime(&mut self, dictionary: &[u8]) -> GzipResult<()> {
+        self.size += dictionary.len();
+        self.tx
+            .send(ChannelData::Prime(dictionary.to_vec()))
+            .or(Err(GzipError::InternalError))
+    }
+
+    fn set_text(&mut self, text: bool) -> GzipResult<()> {
+        self.tx
+            .send(ChannelData::Text(text))
+            .or(Err(GzipError::InternalError))
+    }
+
+    fn digest(&mut self) -> GzipResult<StreamDigest> {
+        let (reply, digest) = channel();
+        self.tx
+            .send(ChannelData::Digest(reply))
+            .or(Err(GzipError::InternalError))?;
+        digest.recv().or(Err(GzipError::InternalError))
+    }
 }
 
 impl ReceiverBuffer {
     fn put_u8(&mut self, data: u8) -> GzipResult<()> {
         self.buffer[self.pos] = data;
-        self.pos = (self.pos + 1) & 32767;
+        self.pos = (self.pos + 1) & self.mask;
         self.size += 1;
         self.output.put_u8(data)
     }
@@ -94,24 +152,42 @@ impl ReceiverBuffer {
     fn put_data(&mut self, data: Vec<u8>) -> GzipResult<()> {
         for d in &data {
             self.buffer[self.pos] = *d;
-            self.pos = (self.pos + 1) & 32767;
+            self.pos = (self.pos + 1) & self.mask;
         }
         self.size += data.len();
         self.output.put_data(&data)
     }
 
+    fn reset(&mut self, checksum: Box<Checksum>, window: usize) {
+        if self.buffer.len() != window {
+            self.buffer = vec![0; window];
+            self.mask = window - 1;
+            self.pos = 0;
+        }
+        self.size = 0;
+        self.output.reset(checksum);
+    }
+
+    fn prime(&mut self, data: Vec<u8>) {
+        for d in &data {
+            self.buffer[self.pos] = *d;
+            self.pos = (self.pos + 1) & self.mask;
+        }
+        self.size += data.len();
+    }
+
     fn copy_window(&mut self, distance: u32, length: u32) -> GzipResult<()> {
         let distance = distance as usize;
-        if distance > self.size {
+        if distance > self.size || distance > self.buffer.len() {
             return Err(GzipError::InvalidDeflateStream);
         }
-        let index : usize = self.pos + 32768 - distance;
+        let index : usize = self.pos + self.buffer.len() - distance;
         verbose!(2, "window char: ");
         for i in 0..length {
-            let data = self.buffer[(index + i as usize) & 32767];
+            let data = self.buffer[(index + i as usize) & self.mask];
             verbose!(2, "-- {}", data as u8 as char);
             self.buffer[self.pos] = data;
-            self.pos = (self.pos + 1) & 32767;
+            self.pos = (self.pos + 1) & self.mask;
             self.output.put_u8(data)?;
         }
         self.size += length as usize;
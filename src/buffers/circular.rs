@@ -1,26 +1,31 @@
 use errors::{GzipResult, GzipError};
 use buffers::outputbuffer::OutputBuffer;
 use sinks::bytesink::{ByteSink, ByteSinkProvider};
+use sinks::checksumsink::{ChecksumSink, StreamDigest};
+use checksums::checksum::Checksum;
+use checksums::crc32::Crc32;
 use context::VERBOSE;
 
 pub struct CircularBuffer {
     buffer: Vec<u8>,
     pos: usize,
     size: usize,
-    output: Box<ByteSink>
+    mask: usize,
+    output: ChecksumSink
 }
 
 impl CircularBuffer {
     pub fn new(provider: ByteSinkProvider) -> GzipResult<Self> {
-        let output = provider()?;
-        Ok(CircularBuffer{ buffer: vec![0; 32768], pos: 0, size: 0, output })
+        let output = ChecksumSink::new(provider()?, Box::new(Crc32::new()));
+        Ok(CircularBuffer{
+            buffer: vec![0; 32768], pos: 0, size: 0, mask: 32767, output })
     }
 }
 
 impl OutputBuffer for CircularBuffer {
     fn put_u8(&mut self, data: u8) -> GzipResult<()> {
         self.buffer[self.pos] = data;
-        self.pos = (self.pos + 1) & 32767;
+        self.pos = (self.pos + 1) & self.mask;
         self.size += 1;
         self.output.put_u8(data)
     }
@@ -28,7 +33,7 @@ impl OutputBuffer for CircularBuffer {
     fn put_data(&mut self, data: Vec<u8>) -> GzipResult<()> {
         for d in &data {
             self.buffer[self.pos] = *d;
-            self.pos = (self.pos + 1) & 32767;
+            self.pos = (self.pos + 1) & self.mask;
         }
         self.size += data.len();
         self.output.put_data(&data)
@@ -36,22 +41,52 @@ impl OutputBuffer for CircularBuffer {
 
     fn copy_window(&mut self, distance: u32, length: u32) -> GzipResult<()> {
         let distance = distance as usize;
-        if distance > self.size {
+        if distance > self.size || distance > self.buffer.len() {
             println!("d {} {}", distance, self.size);
             return Err(GzipError::InvalidDeflateStream);
         }
-        let index : usize = self.pos + 32768 - distance;
+        let index : usize = self.pos + self.buffer.len() - distance;
         verbose!(2, "window char: ");
         for i in 0..length {
-            let data = self.buffer[(index + i as usize) & 32767];
+            let data = self.buffer[(index + i as usize) & self.mask];
             verbose!(2, "-- {}", data as u8 as char);
             self.buffer[self.pos] = data;
-            self.pos = (self.pos + 1) & 32767;
+            self.pos = (self.pos + 1) & self.mask;
             self.output.put_u8(data)?;
         }
         self.size += length as usize;
         Ok(())
     }
+
+    fn reset(&mut self, checksum: Box<Checksum>, window: usize)
+        -> GzipResult<()> {
+
+        if self.buffer.len() != window {
+            self.buffer = vec![0; window];
+            self.mask = window - 1;
+            self.pos = 0;
+        }
+        self.size = 0;
+        self.output.reset(checksum);
+        Ok(())
+    }
+
+    fn prime(&mut self, dictionary: &[u8]) -> GzipResult<()> {
+        for d in dictionary {
+            self.buffer[self.pos] = *d;
+            self.pos = (self.pos + 1) & self.mask;
+        }
+        self.size += dictionary.len();
+        Ok(())
+    }
+
+    fn set_text(&mut self, text: bool) -> GzipResult<()> {
+        self.output.set_text(text)
+    }
+
+    fn digest(&mut self) -> GzipResult<StreamDigest> {
+        Ok(self.output.digest())
+    }
 }
 
 
@@ -1,19 +1,41 @@
 use errors::{GzipResult, GzipError};
 use buffers::outputbuffer::OutputBuffer;
 use sinks::bytesink::{ByteSink, ByteSinkProvider};
+use sinks::checksumsink::{ChecksumSink, StreamDigest};
+use checksums::checksum::Checksum;
+use checksums::crc32::Crc32;
 use std::ptr;
 
 pub struct CopyBuffer {
     buffer: Vec<u8>,
     pos: usize,
     size: usize,
-    output: Box<ByteSink>
+    flushed: usize,
+    mask: usize,
+    output: ChecksumSink
 }
 
 impl CopyBuffer {
     pub fn new(provider: ByteSinkProvider) -> GzipResult<Self> {
-        let output = provider()?;
-        Ok(CopyBuffer{ buffer: vec![0; 32768], pos: 0, size: 0, output })
+        let output = ChecksumSink::new(provider()?, Box::new(Crc32::new()));
+        Ok(CopyBuffer{
+            buffer: vec![0; 32768], pos: 0, size: 0, flushed: 0, mask: 32767,
+            output })
+    }
+
+    fn flush(&mut self) -> GzipResult<()> {
+        if self.pos > self.flushed {
+            self.output.put_data(&self.buffer[self.flushed..self.pos])?;
+        }
+        self.flushed = self.pos;
+        Ok(())
+    }
+
+    fn wrap(&mut self) -> GzipResult<()> {
+        self.output.put_data(&self.buffer[self.flushed..])?;
+        self.pos = 0;
+        self.flushed = 0;
+        Ok(())
     }
 }
 
@@ -22,9 +44,8 @@ impl OutputBuffer for CopyBuffer {
         self.buffer[self.pos] = data;
         self.size += 1;
         self.pos += 1;
-        if self.pos >= 32768 {
-            self.pos = 0;
-            self.output.put_data(&self.buffer)?;
+        if self.pos > self.mask {
+            self.wrap()?;
         }
         Ok(())
     }
@@ -32,11 +53,11 @@ impl OutputBuffer for CopyBuffer {
     fn copy_window(&mut self, distance: u32, length: u32) -> GzipResult<()> {
         let distance = distance as usize;
         let length = length as usize;
-        if distance > self.size {
+        if distance > self.size || distance > self.buffer.len() {
             return Err(GzipError::InvalidDeflateStream);
         }
         if self.pos >= distance &&
-            self.pos + length < 32767 &&
+            self.pos + length < self.mask &&
             distance > length {
 
             let begin = self.buffer.as_mut_ptr();
@@ -48,26 +69,59 @@ impl OutputBuffer for CopyBuffer {
             }
             self.pos += length;
         } else {
-            let index : usize = self.pos + 32768 - distance;
+            let index : usize = self.pos + self.buffer.len() - distance;
             for i in 0..length {
-                let data = self.buffer[(index + i) & 32767];
+                let data = self.buffer[(index + i) & self.mask];
                 self.buffer[self.pos] = data;
                 self.pos += 1;
-                if self.pos >= 32768 {
-                    self.pos = 0;
-                    self.output.put_data(&self.buffer)?;
+                if self.pos > self.mask {
+                    self.wrap()?;
                 }
             }
         }
         self.size += length as usize;
         Ok(())
     }
+
+    fn reset(&mut self, checksum: Box<Checksum>, window: usize)
+        -> GzipResult<()> {
+
+        self.flush()?;
+        if self.buffer.len() != window {
+            self.buffer = vec![0; window];
+            self.mask = window - 1;
+            self.pos = 0;
+            self.flushed = 0;
+        }
+        self.size = 0;
+        self.output.reset(checksum);
+        Ok(())
+    }
+
+    fn prime(&mut self, dictionary: &[u8]) -> GzipResult<()> {
+        self.flush()?;
+        for d in dictionary {
+            self.buffer[self.pos] = *d;
+            self.pos = (self.pos + 1) & self.mask;
+        }
+        self.flushed = self.pos;
+        self.size += dictionary.len();
+        Ok(())
+    }
+
+    fn set_text(&mut self, text: bool) -> GzipResult<()> {
+        self.flush()?;
+        self.output.set_text(text)
+    }
+
+    fn digest(&mut self) -> GzipResult<StreamDigest> {
+        self.flush()?;
+        Ok(self.output.digest())
+    }
 }
 
 impl Drop for CopyBuffer {
     fn drop(&mut self) {
-        if self.pos > 0 {
-            self.output.put_data(&self.buffer[0..self.pos]).unwrap();
-        }
+        self.flush().unwrap();
     }
 }
@@ -1,17 +1,21 @@
 use errors::{GzipResult, GzipError};
 use buffers::outputbuffer::OutputBuffer;
 use sinks::bytesink::{ByteSink, ByteSinkProvider};
+use sinks::checksumsink::{ChecksumSink, StreamDigest};
+use checksums::checksum::Checksum;
+use checksums::crc32::Crc32;
 use context::VERBOSE;
 
 pub struct InMemoryBuffer {
     buffer: Vec<u8>,
-    output: Box<ByteSink>
+    window: usize,
+    output: ChecksumSink
 }
 
 impl InMemoryBuffer {
     pub fn new(provider: ByteSinkProvider) -> GzipResult<Self> {
-        let output = provider()?;
-        Ok(InMemoryBuffer{ buffer: vec![], output })
+        let output = ChecksumSink::new(provider()?, Box::new(Crc32::new()));
+        Ok(InMemoryBuffer{ buffer: vec![], window: 32768, output })
     }
 }
 
@@ -27,7 +31,8 @@ impl OutputBuffer for InMemoryBuffer {
     }
 
     fn copy_window(&mut self, distance: u32, length: u32) -> GzipResult<()> {
-        if distance as usize > self.buffer.len() {
+        if distance as usize > self.buffer.len() ||
+            distance as usize > self.window {
             return Err(GzipError::InvalidDeflateStream);
         }
         let index : usize = self.buffer.len() - distance as usize;
@@ -40,5 +45,27 @@ impl OutputBuffer for InMemoryBuffer {
         }
         Ok(())
     }
+
+    fn reset(&mut self, checksum: Box<Checksum>, window: usize)
+        -> GzipResult<()> {
+
+        self.buffer.clear();
+        self.window = window;
+        self.output.reset(checksum);
+        Ok(())
+    }
+
+    fn prime(&mut self, dictionary: &[u8]) -> GzipResult<()> {
+        self.buffer.extend_from_slice(dictionary);
+        Ok(())
+    }
+
+    fn set_text(&mut self, text: bool) -> GzipResult<()> {
+        self.output.set_text(text)
+    }
+
+    fn digest(&mut self) -> GzipResult<StreamDigest> {
+        Ok(self.output.digest())
+    }
 }
 
@@ -3,5 +3,6 @@ pub mod inmemory;
 pub mod circular;
 pub mod channel;
 pub mod copy;
+pub mod buffermethod;
 
 
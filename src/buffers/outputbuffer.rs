@@ -1,4 +1,6 @@
 use errors::GzipResult;
+use sinks::checksumsink::StreamDigest;
+use checksums::checksum::Checksum;
 
 pub trait OutputBuffer {
     fn put_u8(&mut self, data: u8) -> GzipResult<()>;
@@ -11,6 +13,14 @@ pub trait OutputBuffer {
     }
 
     fn copy_window(&mut self, distance: u32, length: u32) -> GzipResult<()>;
-}
 
+    fn reset(&mut self, checksum: Box<Checksum>, window: usize)
+        -> GzipResult<()>;
+
+    fn prime(&mut self, dictionary: &[u8]) -> GzipResult<()>;
+
+    fn set_text(&mut self, text: bool) -> GzipResult<()>;
+
+    fn digest(&mut self) -> GzipResult<StreamDigest>;
+}
 
@@ -0,0 +1,60 @@
+use checksums::checksum::Checksum;
+
+const BASE : u32 = 65521;
+
+// Largest n such that 255n(n+1)/2 + (n+1)(BASE-1) fits in 32 bits.
+const NMAX : usize = 5552;
+
+pub struct Adler32 {
+    a: u32,
+    b: u32
+}
+
+impl Adler32 {
+    pub fn new() -> Self {
+        Adler32{ a: 1, b: 0 }
+    }
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checksum for Adler32 {
+    fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(NMAX) {
+            for d in chunk {
+                self.a += *d as u32;
+                self.b += self.a;
+            }
+            self.a %= BASE;
+            self.b %= BASE;
+        }
+    }
+
+    fn update_u8(&mut self, data: u8) {
+        self.a = (self.a + data as u32) % BASE;
+        self.b = (self.b + self.a) % BASE;
+    }
+
+    fn value(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+#[test]
+fn adler32_matches_known_values() {
+    let mut adler = Adler32::new();
+    assert!(adler.value() == 1);
+    adler.update(b"Wikipedia");
+    assert!(adler.value() == 0x11E60398);
+    let mut adler = Adler32::new();
+    adler.update(&vec![255; 100000]);
+    let mut slow = Adler32::new();
+    for _ in 0..100000 {
+        slow.update_u8(255);
+    }
+    assert!(adler.value() == slow.value());
+}
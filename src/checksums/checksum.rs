@@ -0,0 +1,9 @@
+pub trait Checksum : Send {
+    fn update(&mut self, data: &[u8]);
+
+    fn update_u8(&mut self, data: u8) {
+        self.update(&[data]);
+    }
+
+    fn value(&self) -> u32;
+}
@@ -0,0 +1,58 @@
+use checksums::checksum::Checksum;
+
+// The reflected CRC-16 from ARC, also used by LHarc.
+const POLYNOMIAL : u16 = 0xA001;
+
+pub struct Crc16 {
+    table: Vec<u16>,
+    crc: u16
+}
+
+impl Crc16 {
+    pub fn new() -> Self {
+        let mut table = vec![0; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut value = i as u16;
+            for _ in 0..8 {
+                value = if value & 1 > 0 {
+                    POLYNOMIAL ^ (value >> 1)
+                } else {
+                    value >> 1
+                };
+            }
+            *entry = value;
+        }
+        Crc16{ table, crc: 0 }
+    }
+}
+
+impl Default for Crc16 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checksum for Crc16 {
+    fn update(&mut self, data: &[u8]) {
+        for d in data {
+            self.update_u8(*d);
+        }
+    }
+
+    fn update_u8(&mut self, data: u8) {
+        let index = ((self.crc ^ data as u16) & 255) as usize;
+        self.crc = self.table[index] ^ (self.crc >> 8);
+    }
+
+    fn value(&self) -> u32 {
+        self.crc as u32
+    }
+}
+
+#[test]
+fn crc16_matches_known_values() {
+    let mut crc = Crc16::new();
+    assert!(crc.value() == 0);
+    crc.update(b"123456789");
+    assert!(crc.value() == 0xBB3D);
+}
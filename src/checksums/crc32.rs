@@ -0,0 +1,59 @@
+use checksums::checksum::Checksum;
+
+const POLYNOMIAL : u32 = 0xEDB88320;
+
+pub struct Crc32 {
+    table: Vec<u32>,
+    crc: u32
+}
+
+impl Crc32 {
+    pub fn new() -> Self {
+        let mut table = vec![0; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            let mut value = i as u32;
+            for _ in 0..8 {
+                value = if value & 1 > 0 {
+                    POLYNOMIAL ^ (value >> 1)
+                } else {
+                    value >> 1
+                };
+            }
+            *entry = value;
+        }
+        Crc32{ table, crc: 0xFFFFFFFF }
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Checksum for Crc32 {
+    fn update(&mut self, data: &[u8]) {
+        let mut crc = self.crc;
+        for d in data {
+            crc = self.table[((crc ^ *d as u32) & 255) as usize] ^ (crc >> 8);
+        }
+        self.crc = crc;
+    }
+
+    fn update_u8(&mut self, data: u8) {
+        let index = ((self.crc ^ data as u32) & 255) as usize;
+        self.crc = self.table[index] ^ (self.crc >> 8);
+    }
+
+    fn value(&self) -> u32 {
+        self.crc ^ 0xFFFFFFFF
+    }
+}
+
+#[test]
+fn crc32_matches_known_values() {
+    let mut crc = Crc32::new();
+    assert!(crc.value() == 0);
+    crc.update(b"123456789");
+    assert!(crc.value() == 0xCBF43926);
+}
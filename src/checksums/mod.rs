@@ -0,0 +1,4 @@
+pub mod checksum;
+pub mod crc32;
+pub mod adler32;
+pub mod crc16;
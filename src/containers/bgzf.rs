@@ -0,0 +1,172 @@
+use errors::{GzipResult, GzipError};
+use sources::bytesource::ByteSource;
+use sources::mapsource::MapSource;
+use sources::limitsource::LimitSource;
+use sources::adaptermethod::AdapterMethod;
+use sinks::bytesink::{ByteSink, ByteSinkProvider};
+use sinks::rangesink::RangeSink;
+use sinks::bitsinkadapter::BitSinkAdapter;
+use blocks::deflate::{DeflateDecoder, DeflateEncoder};
+use blocks::window::DeflateFormat;
+use checksums::checksum::Checksum;
+use checksums::crc32::Crc32;
+use buffers::outputbuffer::OutputBuffer;
+use buffers::buffermethod::BufferMethod;
+use containers::gzip::GzipHeader;
+use context::VERBOSE;
+
+// Same input size per block as bgzip, which keeps every block under 64K
+// even when the data doesn't compress and goes in stored blocks.
+const BLOCK_DATA_SIZE : usize = 0xFF00;
+
+// Gzip header with FEXTRA and a BC subfield, minus the block size.
+const BLOCK_HEADER : [u8; 16] =
+    [31, 139, 8, 4, 0, 0, 0, 0, 0, 255, 6, 0, b'B', b'C', 2, 0];
+const BLOCK_HEADER_SIZE : usize = 18;
+const BLOCK_TRAILER_SIZE : usize = 8;
+
+// The empty block that marks the end of a BGZF file.
+const EOF_BLOCK : [u8; 28] =
+    [31, 139, 8, 4, 0, 0, 0, 0, 0, 255, 6, 0, b'B', b'C', 2, 0,
+     27, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+// A virtual offset is the compressed offset of a block shifted left by 16,
+// plus the offset inside the uncompressed block.
+pub fn split_virtual_offset(voffset: u64) -> (u64, u64) {
+    (voffset >> 16, voffset & 0xFFFF)
+}
+
+// Returns the total block size from the BC subfield, which stores it
+// minus one. The block must at least fit its header and trailer.
+pub fn block_size(header: &GzipHeader, header_size: u64) -> GzipResult<u64> {
+    let extra = header.extra.as_ref().ok_or(GzipError::NotABgzfFile)?;
+    extra.subfields.iter()
+        .find(|s| s.SI1 == b'B' && s.SI2 == b'C' && s.data.len() == 2)
+        .map(|s| (s.data[0] as u64 | (s.data[1] as u64) << 8) + 1)
+        .filter(|&size| size >= header_size + BLOCK_TRAILER_SIZE as u64)
+        .ok_or(GzipError::NotABgzfFile)
+}
+
+pub struct BgzfReader {
+    input: MapSource
+}
+
+impl BgzfReader {
+    pub fn open(name: &str) -> GzipResult<Self> {
+        Ok(BgzfReader{ input: MapSource::from_file(name)? })
+    }
+
+    // Only the blocks from the one containing voffset up to the end of
+    // the requested length are read, everything before is skipped.
+    pub fn decode(&mut self, voffset: u64, length: Option<u64>,
+                  sink: ByteSinkProvider, buffer: BufferMethod,
+                  adapter: AdapterMethod) -> GzipResult<()> {
+
+        let (start, skip) = split_virtual_offset(voffset);
+        if start >= self.input.size() {
+            return Err(GzipError::InvalidVirtualOffset);
+        }
+        let end = length.map(|length| skip + length);
+        let sink = RangeSink::provider(sink, skip, length);
+        let mut output = buffer.create(sink)?;
+        let mut offset = start;
+        let mut decoded = 0;
+        while offset < self.input.size() &&
+            end.is_none_or(|end| decoded < end) {
+            let (size, isize) =
+                self.decode_block(offset, output.as_mut(), adapter)?;
+            if offset == start && isize < skip {
+                return Err(GzipError::InvalidVirtualOffset);
+            }
+            offset += size;
+            decoded += isize;
+        }
+        Ok(())
+    }
+
+    fn decode_block(&mut self, offset: u64, output: &mut OutputBuffer,
+                    adapter: AdapterMethod) -> GzipResult<(u64, u64)> {
+
+        self.input.seek(offset);
+        let header = GzipHeader::read(&mut self.input)?;
+        let size = block_size(&header, self.input.position() - offset)?;
+        if offset + size > self.input.size() {
+            return Err(GzipError::TruncatedFile);
+        }
+        output.reset(Box::new(Crc32::new()),
+                     DeflateFormat::Deflate.window_size())?;
+        {
+            // The deflate stream must end exactly where the trailer starts.
+            let end = offset + size - BLOCK_TRAILER_SIZE as u64;
+            let length = end - self.input.position();
+            let mut limited = LimitSource::new(&mut self.input, length);
+            let mut bits = adapter.wrap(&mut limited);
+            match DeflateDecoder::new(bits.as_mut(), output,
+                                      DeflateFormat::Deflate).decode() {
+                Err(GzipError::TruncatedFile) =>
+                    return Err(GzipError::BgzfBlockSizeMismatch),
+                result => result?
+            }
+            if bits.position() != end {
+                return Err(GzipError::BgzfBlockSizeMismatch);
+            }
+        }
+        self.input.seek(offset + size - BLOCK_TRAILER_SIZE as u64);
+        let crc32 = self.input.get_u32()?;
+        let isize = self.input.get_u32()?;
+        let digest = output.digest()?;
+        verbose!(1, "Block at offset {}: {} bytes, CRC32 {:08x}",
+                 offset, isize, crc32);
+        if digest.checksum != crc32 {
+            return Err(GzipError::CRC32Mismatch);
+        }
+        if digest.size != isize as u64 {
+            return Err(GzipError::ISIZEMismatch);
+        }
+        Ok((size, isize as u64))
+    }
+}
+
+pub struct BgzfWriter {
+    input: Box<ByteSource>,
+    output: Box<ByteSink>
+}
+
+impl BgzfWriter {
+    pub fn encode(input: Box<ByteSource>, output: Box<ByteSink>)
+        -> GzipResult<()> {
+
+        let mut writer = BgzfWriter{ input, output };
+        let mut offset = 0;
+        loop {
+            let data = writer.input.get_data(BLOCK_DATA_SIZE)?;
+            if data.is_empty() {
+                break;
+            }
+            let size = writer.encode_block(&data)?;
+            verbose!(1, "Block at offset {}: {} bytes", offset, data.len());
+            offset += size;
+        }
+        writer.output.put_data(&EOF_BLOCK)
+    }
+
+    // Each block is a complete deflate stream, so it can be inflated on
+    // its own.
+    fn encode_block(&mut self, data: &[u8]) -> GzipResult<u64> {
+        let mut deflate = vec![];
+        {
+            let mut bits = BitSinkAdapter::new(&mut deflate);
+            DeflateEncoder::new(&mut bits).encode(data)?;
+            bits.align()?;
+        }
+        let size = BLOCK_HEADER_SIZE + deflate.len() + BLOCK_TRAILER_SIZE;
+        let mut crc = Crc32::new();
+        crc.update(data);
+        self.output.put_data(&BLOCK_HEADER)?;
+        self.output.put_data(&((size - 1) as u16).to_le_bytes())?;
+        self.output.put_data(&deflate)?;
+        self.output.put_data(&crc.value().to_le_bytes())?;
+        self.output.put_data(&(data.len() as u32).to_le_bytes())?;
+        Ok(size as u64)
+    }
+}
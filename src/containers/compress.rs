@@ -0,0 +1,165 @@
+use errors::{GzipResult, GzipError};
+use sources::bitsource::BitSource;
+use sources::bytesource::ByteSource;
+use sources::adaptermethod::AdapterMethod;
+use blocks::window::DeflateFormat;
+use checksums::crc32::Crc32;
+use buffers::outputbuffer::OutputBuffer;
+use context::VERBOSE;
+
+const BLOCK_MODE : u8 = 0x80;
+const RESERVED : u8 = 0x60;
+const MAX_BITS_MASK : u8 = 0x1f;
+const INIT_BITS : u8 = 9;
+const MAX_BITS : u8 = 16;
+const CLEAR : u32 = 256;
+
+pub struct CompressDecoder<'a> {
+    input: &'a mut BitSource,
+    output: &'a mut OutputBuffer,
+    prefix: Vec<u16>,
+    suffix: Vec<u8>,
+    stack: Vec<u8>,
+    n_bits: u8,
+    // Codes read since the last change of width, which compress pads to a
+    // multiple of eight.
+    group: u32
+}
+
+impl<'a> CompressDecoder<'a> {
+    pub fn decode(input: Box<ByteSource>, output: Box<OutputBuffer>,
+                  adapter: AdapterMethod) -> GzipResult<()> {
+
+        let mut input = input;
+        let mut output = output;
+        if input.get_u8()? != 31 || input.get_u8()? != 157 {
+            return Err(GzipError::InvalidCompressHeader);
+        }
+        let flags = input.get_u8()?;
+        let max_bits = flags & MAX_BITS_MASK;
+        let block_mode = flags & BLOCK_MODE != 0;
+        if flags & RESERVED != 0 ||
+            !(INIT_BITS..=MAX_BITS).contains(&max_bits) {
+            return Err(GzipError::InvalidCompressHeader);
+        }
+        verbose!(1, "Compress max bits: {}, block mode: {}",
+                 max_bits, block_mode);
+        output.reset(Box::new(Crc32::new()),
+                     DeflateFormat::Deflate.window_size())?;
+        {
+            let mut bits = adapter.wrap(input.as_mut());
+            let mut decoder = CompressDecoder {
+                input: bits.as_mut(),
+                output: output.as_mut(),
+                prefix: vec![0; 1 << MAX_BITS],
+                suffix: (0..1u32 << MAX_BITS).map(|i| i as u8).collect(),
+                stack: vec![],
+                n_bits: INIT_BITS,
+                group: 0
+            };
+            decoder.decode_codes(max_bits, block_mode)?;
+        }
+        let digest = output.digest()?;
+        verbose!(1, "Decoded {} bytes, CRC32: {:08x}",
+                 digest.size, digest.checksum);
+        Ok(())
+    }
+
+    // The stream has no end marker, it stops at the last complete code.
+    fn decode_codes(&mut self, max_bits: u8, block_mode: bool)
+        -> GzipResult<()> {
+
+        let max_max_code = 1u32 << max_bits;
+        let mut max_code = (1u32 << self.n_bits) - 1;
+        let mut free_ent = if block_mode { CLEAR + 1 } else { CLEAR };
+        let mut old_code = None;
+        let mut fin_char = 0u8;
+        loop {
+            if free_ent > max_code {
+                if !self.skip_group()? {
+                    return Ok(());
+                }
+                self.n_bits += 1;
+                max_code = if self.n_bits == max_bits {
+                    max_max_code
+                } else {
+                    (1 << self.n_bits) - 1
+                };
+            }
+            let code = match self.get_code()? {
+                Some(code) => code,
+                None => return Ok(())
+            };
+            let old = match old_code {
+                Some(old) => old,
+                None => {
+                    if code >= CLEAR {
+                        return Err(GzipError::InvalidLzwCode);
+                    }
+                    fin_char = code as u8;
+                    self.output.put_u8(fin_char)?;
+                    old_code = Some(code);
+                    continue;
+                }
+            };
+            if code == CLEAR && block_mode {
+                verbose!(2, "Clear code at bit {}", self.input.bit_position());
+                free_ent = CLEAR;
+                if !self.skip_group()? {
+                    return Ok(());
+                }
+                self.n_bits = INIT_BITS;
+                max_code = (1 << self.n_bits) - 1;
+                continue;
+            }
+            self.stack.clear();
+            let mut current = code;
+            if current >= free_ent {
+                // The KwKwK case: the code being defined by this very step.
+                if current > free_ent {
+                    return Err(GzipError::InvalidLzwCode);
+                }
+                self.stack.push(fin_char);
+                current = old;
+            }
+            while current >= CLEAR {
+                self.stack.push(self.suffix[current as usize]);
+                current = self.prefix[current as usize] as u32;
+            }
+            fin_char = current as u8;
+            self.stack.push(fin_char);
+            for &byte in self.stack.iter().rev() {
+                self.output.put_u8(byte)?;
+            }
+            if free_ent < max_max_code {
+                self.prefix[free_ent as usize] = old as u16;
+                self.suffix[free_ent as usize] = fin_char;
+                free_ent += 1;
+            }
+            old_code = Some(code);
+        }
+    }
+
+    fn get_code(&mut self) -> GzipResult<Option<u32>> {
+        match self.input.get_bits_rev(self.n_bits) {
+            Ok(code) => {
+                self.group += 1;
+                Ok(Some(code))
+            },
+            Err(GzipError::TruncatedFile) => Ok(None),
+            Err(error) => Err(error)
+        }
+    }
+
+    // compress writes codes in groups of eight, so a change of width skips
+    // the rest of the current group.
+    fn skip_group(&mut self) -> GzipResult<bool> {
+        while !self.group.is_multiple_of(8) {
+            if self.get_code()?.is_none() {
+                return Ok(false);
+            }
+        }
+        self.group = 0;
+        Ok(true)
+    }
+}
@@ -0,0 +1,59 @@
+use errors::{GzipResult, GzipError};
+use sources::bytesource::ByteSource;
+use sources::prefixsource::PrefixSource;
+use containers::zlib::ZlibHeader;
+use blocks::window::DeflateFormat;
+use context::VERBOSE;
+
+#[derive(Debug, PartialEq)]
+pub enum Container {
+    Gzip,
+    Zlib,
+    Raw(DeflateFormat),
+    Compress,
+    Pack,
+    Lzh
+}
+
+pub struct ContainerDetector;
+
+impl ContainerDetector {
+    pub fn detect(input: Box<ByteSource>, raw_fallback: bool)
+        -> GzipResult<(Container, Box<ByteSource>)> {
+
+        let mut input = input;
+        let first = input.get_u8()?;
+        let second = input.get_u8()?;
+        let mut prefix = vec![first, second];
+        let container = if first == 31 && second == 139 {
+            Container::Gzip
+        } else if ZlibHeader::is_zlib(first, second) {
+            Container::Zlib
+        } else if first == 31 && second == 157 {
+            Container::Compress
+        } else if first == 31 && second == 30 {
+            Container::Pack
+        } else if first == 31 && second == 160 ||
+            Self::is_lharc(&mut prefix, input.as_mut()) {
+            Container::Lzh
+        } else if raw_fallback {
+            Container::Raw(DeflateFormat::Deflate)
+        } else {
+            return Err(GzipError::UnknownContainer);
+        };
+        verbose!(1, "Detected container: {:?}", container);
+        Ok((container, Box::new(PrefixSource::new(prefix, input))))
+    }
+
+    // LHarc headers have no magic number, only the method name like -lh5-
+    // after the header size and checksum.
+    fn is_lharc(prefix: &mut Vec<u8>, input: &mut ByteSource) -> bool {
+        while prefix.len() < 7 {
+            match input.get_u8() {
+                Ok(byte) => prefix.push(byte),
+                Err(_) => return false
+            }
+        }
+        &prefix[2..5] == b"-lh" && prefix[6] == b'-'
+    }
+}
@@ -0,0 +1,164 @@
+use errors::{GzipResult, GzipError};
+use sources::bytesource::ByteSource;
+use sources::mapsource::MapSource;
+use sources::adaptermethod::AdapterMethod;
+use sinks::bytesink::{ByteSink, ByteSinkProvider};
+use sinks::rangesink::RangeSink;
+use sinks::bitsinkadapter::BitSinkAdapter;
+use blocks::deflate::{DeflateDecoder, DeflateEncoder};
+use blocks::window::DeflateFormat;
+use checksums::checksum::Checksum;
+use checksums::crc32::Crc32;
+use buffers::buffermethod::BufferMethod;
+use containers::gzip::GzipHeader;
+use context::VERBOSE;
+
+// Same chunk length as dictzip, small enough that a chunk that doesn't
+// compress still fits the 16-bit compressed length in stored blocks.
+const CHUNK_LENGTH : usize = 58315;
+const RA_VERSION : u16 = 1;
+const MAX_CHUNKS : usize = (65535 - 10) / 2;
+
+pub struct DictzipReader {
+    input: MapSource,
+    chunk_length: u64,
+    offsets: Vec<u64>,
+    size: u64
+}
+
+impl DictzipReader {
+    pub fn open(name: &str) -> GzipResult<Self> {
+        let mut input = MapSource::from_file(name)?;
+        let header = GzipHeader::read(&mut input)?;
+        let mut offset = input.position();
+        let data = header.extra.as_ref()
+            .and_then(|extra| extra.subfields.iter()
+                      .find(|s| s.SI1 == b'R' && s.SI2 == b'A'))
+            .map(|subfield| &subfield.data)
+            .ok_or(GzipError::NotADictzipFile)?;
+        let field = |i: usize| {
+            data[2 * i] as u16 | (data[2 * i + 1] as u16) << 8
+        };
+        if data.len() < 6 || field(0) != RA_VERSION ||
+            data.len() != 6 + 2 * field(2) as usize {
+            return Err(GzipError::NotADictzipFile);
+        }
+        let mut offsets = vec![];
+        for i in 0..field(2) as usize {
+            offsets.push(offset);
+            offset += field(3 + i) as u64;
+        }
+        offsets.push(offset);
+        let end = input.size();
+        if end < 4 {
+            return Err(GzipError::TruncatedFile);
+        }
+        input.seek(end - 4);
+        let size = input.get_u32()? as u64;
+        verbose!(1, "Dictzip chunks: {} of {} bytes, {} bytes total",
+                 offsets.len() - 1, field(1), size);
+        Ok(DictzipReader {
+            input, chunk_length: field(1) as u64, offsets, size
+        })
+    }
+
+    // Only the chunks that overlap the range are inflated, each one on its
+    // own since full flushes leave no references across chunks.
+    pub fn decode(&mut self, start: u64, length: Option<u64>,
+                  sink: ByteSinkProvider, buffer: BufferMethod,
+                  adapter: AdapterMethod) -> GzipResult<()> {
+
+        if start > self.size || self.chunk_length == 0 {
+            return Err(GzipError::InvalidRange);
+        }
+        let end = length.map_or(self.size,
+                                |length| self.size.min(start + length));
+        let first = (start / self.chunk_length) as usize;
+        let last = end.div_ceil(self.chunk_length) as usize;
+        let sink = RangeSink::provider(
+            sink, start % self.chunk_length, Some(end - start));
+        let mut output = buffer.create(sink)?;
+        for chunk in first..last.min(self.offsets.len() - 1) {
+            verbose!(1, "Chunk {} at offset {}", chunk, self.offsets[chunk]);
+            self.input.seek(self.offsets[chunk]);
+            output.reset(Box::new(Crc32::new()),
+                         DeflateFormat::Deflate.window_size())?;
+            let mut bits = adapter.wrap(&mut self.input);
+            DeflateDecoder::new(bits.as_mut(), output.as_mut(),
+                                DeflateFormat::Deflate)
+                .decode_until(self.offsets[chunk + 1])?;
+        }
+        Ok(())
+    }
+}
+
+pub struct DictzipWriter {
+    output: Box<ByteSink>
+}
+
+impl DictzipWriter {
+    // The chunk table goes in the header, so every chunk is compressed
+    // before anything is written, but only the compressed chunks are kept.
+    pub fn encode(input: Box<ByteSource>, output: Box<ByteSink>)
+        -> GzipResult<()> {
+
+        let mut input = input;
+        let mut crc = Crc32::new();
+        let mut size : u64 = 0;
+        let mut compressed = vec![];
+        let mut data = input.get_data(CHUNK_LENGTH)?;
+        loop {
+            let next = input.get_data(CHUNK_LENGTH)?;
+            crc.update(&data);
+            size += data.len() as u64;
+            compressed.push(Self::encode_chunk(&data, next.is_empty())?);
+            if compressed.len() > MAX_CHUNKS {
+                return Err(GzipError::DictzipTooLarge);
+            }
+            if next.is_empty() {
+                break;
+            }
+            data = next;
+        }
+        let mut writer = DictzipWriter{ output };
+        // An empty input still needs a final block, but has no chunks.
+        let chunks = if size == 0 { 0 } else { compressed.len() };
+        writer.encode_header(&compressed[..chunks])?;
+        for chunk in &compressed {
+            writer.output.put_data(chunk)?;
+        }
+        writer.output.put_data(&crc.value().to_le_bytes())?;
+        writer.output.put_data(&(size as u32).to_le_bytes())
+    }
+
+    fn encode_header(&mut self, chunks: &[Vec<u8>]) -> GzipResult<()> {
+        let length = 6 + 2 * chunks.len() as u16;
+        self.output.put_data(&[31, 139, 8, 4, 0, 0, 0, 0, 0, 255])?;
+        self.output.put_data(&(length + 4).to_le_bytes())?;
+        self.output.put_data(b"RA")?;
+        self.output.put_data(&length.to_le_bytes())?;
+        self.output.put_data(&RA_VERSION.to_le_bytes())?;
+        self.output.put_data(&(CHUNK_LENGTH as u16).to_le_bytes())?;
+        self.output.put_data(&(chunks.len() as u16).to_le_bytes())?;
+        for chunk in chunks {
+            self.output.put_data(&(chunk.len() as u16).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    // Every chunk but the last ends with a full flush, which leaves it
+    // byte aligned and with no references to earlier chunks.
+    fn encode_chunk(data: &[u8], last: bool) -> GzipResult<Vec<u8>> {
+        let mut compressed = vec![];
+        {
+            let mut bits = BitSinkAdapter::new(&mut compressed);
+            if last {
+                DeflateEncoder::new(&mut bits).encode(data)?;
+                bits.align()?;
+            } else {
+                DeflateEncoder::new(&mut bits).full_flush(data)?;
+            }
+        }
+        Ok(compressed)
+    }
+}
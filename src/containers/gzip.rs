@@ -0,0 +1,565 @@
+use std::mem;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, Component};
+use time;
+use encoding::{Encoding, DecoderTrap};
+use encoding::all::ISO_8859_1;
+use errors::{GzipResult, GzipError};
+use sources::bitsource::BitSource;
+use sources::bytesource::ByteSource;
+use sources::checksumsource::ChecksumSource;
+use sources::readsource::ReadSource;
+use sources::adaptermethod::AdapterMethod;
+use sinks::bytesink::ByteSink;
+use sinks::bitsink::BitSink;
+use sinks::bitsinkadapter::BitSinkAdapter;
+use blocks::deflate::{DeflateDecoder, DeflateEncoder};
+use blocks::window::DeflateFormat;
+use checksums::checksum::Checksum;
+use checksums::crc32::Crc32;
+use buffers::outputbuffer::OutputBuffer;
+use context::VERBOSE;
+
+// FLG bitset, bits 5 to 7 are reserved.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GzipFlags(pub u8);
+
+impl GzipFlags {
+    pub const FTEXT : GzipFlags = GzipFlags(1);
+    pub const FHCRC : GzipFlags = GzipFlags(2);
+    pub const FEXTRA : GzipFlags = GzipFlags(4);
+    pub const FNAME : GzipFlags = GzipFlags(8);
+    pub const FCOMMENT : GzipFlags = GzipFlags(16);
+
+    pub fn contains(&self, flag: GzipFlags) -> bool {
+        self.0 & flag.0 == flag.0
+    }
+
+    pub fn has_reserved(&self) -> bool {
+        self.0 >= 0x20
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        [(GzipFlags::FTEXT, "FTEXT"), (GzipFlags::FHCRC, "FHCRC"),
+         (GzipFlags::FEXTRA, "FEXTRA"), (GzipFlags::FNAME, "FNAME"),
+         (GzipFlags::FCOMMENT, "FCOMMENT")].iter()
+            .filter(|&&(flag, _)| self.contains(flag))
+            .map(|&(_, name)| name)
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GzipExtraFlags {
+    #[default]
+    Unspecified,
+    Slowest,
+    Fastest,
+    Unknown(u8)
+}
+
+impl GzipExtraFlags {
+    pub fn from_u8(xfl: u8) -> Self {
+        match xfl {
+            0 => GzipExtraFlags::Unspecified,
+            2 => GzipExtraFlags::Slowest,
+            4 => GzipExtraFlags::Fastest,
+            _ => GzipExtraFlags::Unknown(xfl)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GzipExtraFlags::Unspecified => "none",
+            GzipExtraFlags::Slowest => "maximum compression, slowest algorithm",
+            GzipExtraFlags::Fastest => "fastest algorithm",
+            GzipExtraFlags::Unknown(_) => "unknown"
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GzipOs {
+    Fat,
+    Amiga,
+    Vms,
+    Unix,
+    VmCms,
+    AtariTos,
+    Hpfs,
+    Macintosh,
+    ZSystem,
+    CpM,
+    Tops20,
+    Ntfs,
+    Qdos,
+    AcornRiscos,
+    Unknown(u8)
+}
+
+impl Default for GzipOs {
+    fn default() -> Self {
+        GzipOs::Unknown(255)
+    }
+}
+
+impl GzipOs {
+    pub fn from_u8(os: u8) -> Self {
+        match os {
+            0 => GzipOs::Fat,
+            1 => GzipOs::Amiga,
+            2 => GzipOs::Vms,
+            3 => GzipOs::Unix,
+            4 => GzipOs::VmCms,
+            5 => GzipOs::AtariTos,
+            6 => GzipOs::Hpfs,
+            7 => GzipOs::Macintosh,
+            8 => GzipOs::ZSystem,
+            9 => GzipOs::CpM,
+            10 => GzipOs::Tops20,
+            11 => GzipOs::Ntfs,
+            12 => GzipOs::Qdos,
+            13 => GzipOs::AcornRiscos,
+            _ => GzipOs::Unknown(os)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GzipOs::Fat => "FAT filesystem (MS-DOS, OS/2, NT/Win32)",
+            GzipOs::Amiga => "Amiga",
+            GzipOs::Vms => "VMS (or OpenVMS)",
+            GzipOs::Unix => "Unix",
+            GzipOs::VmCms => "VM/CMS",
+            GzipOs::AtariTos => "Atari TOS",
+            GzipOs::Hpfs => "HPFS filesystem (OS/2, NT)",
+            GzipOs::Macintosh => "Macintosh",
+            GzipOs::ZSystem => "Z-System",
+            GzipOs::CpM => "CP/M",
+            GzipOs::Tops20 => "TOPS-20",
+            GzipOs::Ntfs => "NTFS filesystem (NT)",
+            GzipOs::Qdos => "QDOS",
+            GzipOs::AcornRiscos => "Acorn RISCOS",
+            GzipOs::Unknown(_) => "unknown"
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+pub struct GzipSubfield {
+    pub SI1: u8,
+    pub SI2: u8,
+    pub data: Vec<u8>,
+}
+
+#[allow(non_snake_case)]
+pub struct GzipExtra {
+    pub XLEN: u16,
+    pub subfields: Vec<GzipSubfield>,
+}
+
+#[derive(Default)]
+#[allow(non_snake_case)]
+pub struct GzipHeader {
+    pub ID1: u8,
+    pub ID2: u8,
+    pub CM: u8,
+    pub FLG: GzipFlags,
+    pub MTIME: u32,
+    pub XFL: GzipExtraFlags,
+    pub OS: GzipOs,
+    pub extra: Option<GzipExtra>,
+    pub original_name: Option<String>,
+    pub comment: Option<String>,
+}
+
+// What probing learns without touching the deflate stream.
+pub struct GzipProbe {
+    pub header: GzipHeader,
+    pub header_size: u64,
+}
+
+impl GzipHeader {
+    // Reads only the header, from the current position, so any reader
+    // will do.
+    pub fn probe<R: Read>(reader: &mut R) -> GzipResult<GzipProbe> {
+        let mut input = ReadSource::new(reader.by_ref());
+        let header = GzipHeader::read(&mut input)?;
+        Ok(GzipProbe{ header, header_size: input.position() })
+    }
+
+    pub fn read(input: &mut ByteSource) -> GzipResult<Self> {
+        let mut decoder = HeaderDecoder {
+            input: ChecksumSource::new(input, Box::new(Crc32::new())),
+            header: GzipHeader::default()
+        };
+        decoder.header.ID1 = decoder.input.get_u8()?;
+        decoder.header.ID2 = decoder.input.get_u8()?;
+        decoder.decode()?;
+        Ok(decoder.header)
+    }
+
+    // The name comes from untrusted input, so only a single plain path
+    // component is accepted.
+    pub fn safe_name(&self) -> GzipResult<Option<&str>> {
+        let name = match self.original_name {
+            Some(ref name) => name,
+            None => return Ok(None)
+        };
+        let mut components = Path::new(name).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None)
+                if !name.contains('/') && !name.contains('\\') =>
+                    Ok(Some(name)),
+            _ => Err(GzipError::UnsafeOriginalName)
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+pub struct GzipTrailer {
+    pub CRC32: u32,
+    pub ISIZE: u32,
+}
+
+impl GzipTrailer {
+    // Reads the last 8 bytes of the stream. On multi-member files this is
+    // the trailer of the last member, not of the one at the start.
+    pub fn find<R: Read + Seek>(reader: &mut R) -> Option<GzipTrailer> {
+        reader.seek(SeekFrom::End(-8)).ok()?;
+        let mut input = ReadSource::new(reader.by_ref());
+        Some(GzipTrailer {
+            CRC32: input.get_u32().ok()?,
+            ISIZE: input.get_u32().ok()?,
+        })
+    }
+}
+
+pub struct GzipMember {
+    pub header: GzipHeader,
+    pub trailer: GzipTrailer,
+    pub offset: u64,
+    pub header_size: u64,
+    pub size: u64,
+}
+
+// What to do with bytes after the last member that don't start another
+// gzip member.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TrailingPolicy {
+    Error,
+    Warn,
+    IgnoreZeros
+}
+
+pub struct GzipSummary {
+    pub members: Vec<GzipMember>,
+    pub trailing: Option<u64>,
+}
+
+pub struct GzipDecoder<'a> {
+    input: Box<BitSource + 'a>,
+    output: Box<OutputBuffer>,
+    header: GzipHeader,
+    policy: TrailingPolicy,
+    trailing: Option<u64>
+}
+
+struct HeaderDecoder<'a> {
+    input: ChecksumSource<'a>,
+    header: GzipHeader
+}
+
+impl<'a> GzipDecoder<'a> {
+    pub fn decode(input : Box<ByteSource>, output: Box<OutputBuffer>,
+                  policy: TrailingPolicy, adapter: AdapterMethod)
+        -> GzipResult<GzipSummary> {
+
+        let mut input = input;
+        let mut gzip = GzipDecoder {
+            input: adapter.wrap(input.as_mut()),
+            output: output,
+            header: GzipHeader::default(),
+            policy,
+            trailing: None
+        };
+        let mut members = vec![];
+        let mut offset = gzip.input.position();
+        gzip.header.ID1 = gzip.input.get_u8()?;
+        gzip.header.ID2 = gzip.input.get_u8()?;
+        for member in 1.. {
+            gzip.output.reset(Box::new(Crc32::new()),
+                              DeflateFormat::Deflate.window_size())?;
+            gzip.decode_header()?;
+            gzip.output.set_text(gzip.header.FLG.contains(GzipFlags::FTEXT))?;
+            let header_size = gzip.input.position() - offset;
+            DeflateDecoder::new(gzip.input.as_mut(), gzip.output.as_mut(),
+                                DeflateFormat::Deflate).decode()?;
+            let trailer = gzip.decode_trailer()?;
+            let end = gzip.input.position();
+            verbose!(1, "Member {} at offset {}: {} bytes, CRC32 {:08x}",
+                     member, offset, trailer.ISIZE, trailer.CRC32);
+            members.push(GzipMember {
+                header: mem::take(&mut gzip.header),
+                trailer,
+                offset,
+                header_size,
+                size: end - offset
+            });
+            offset = end;
+            if !gzip.next_member()? {
+                break;
+            }
+        }
+        Ok(GzipSummary{ members, trailing: gzip.trailing })
+    }
+
+    // Anything after a trailer that doesn't start with the gzip magic is
+    // trailing data, handled according to the policy.
+    fn next_member(&mut self) -> GzipResult<bool> {
+        let offset = self.input.position();
+        let mut magic = vec![];
+        for _ in 0..2 {
+            match self.input.get_u8() {
+                Ok(byte) => magic.push(byte),
+                Err(GzipError::TruncatedFile) => break,
+                Err(error) => return Err(error)
+            }
+        }
+        if magic == [31, 139] {
+            self.header.ID1 = magic[0];
+            self.header.ID2 = magic[1];
+            return Ok(true);
+        }
+        if !magic.is_empty() {
+            self.decode_trailing(offset, &magic)?;
+        }
+        Ok(false)
+    }
+
+    fn decode_trailing(&mut self, offset: u64, start: &[u8])
+        -> GzipResult<()> {
+
+        verbose!(1, "Trailing data at offset {}", offset);
+        match self.policy {
+            TrailingPolicy::Error => {
+                return Err(GzipError::TrailingData(offset));
+            },
+            TrailingPolicy::Warn => {},
+            TrailingPolicy::IgnoreZeros => {
+                if start.iter().any(|&byte| byte != 0) {
+                    return Err(GzipError::TrailingData(offset));
+                }
+                loop {
+                    match self.input.get_u8() {
+                        Ok(0) => {},
+                        Ok(_) => return Err(GzipError::TrailingData(offset)),
+                        Err(GzipError::TruncatedFile) => break,
+                        Err(error) => return Err(error)
+                    }
+                }
+            }
+        }
+        self.trailing = Some(offset);
+        Ok(())
+    }
+
+    fn decode_trailer(&mut self) -> GzipResult<GzipTrailer> {
+        let trailer = GzipTrailer {
+            CRC32: self.input.get_u32()?,
+            ISIZE: self.input.get_u32()?,
+        };
+        let digest = self.output.digest()?;
+        verbose!(1, "CRC32: {:08x}, ISIZE: {}", trailer.CRC32, trailer.ISIZE);
+        if digest.checksum != trailer.CRC32 {
+            return Err(GzipError::CRC32Mismatch);
+        }
+        if digest.size as u32 != trailer.ISIZE {
+            return Err(GzipError::ISIZEMismatch);
+        }
+        Ok(trailer)
+    }
+
+    fn decode_header(&mut self) -> GzipResult<()> {
+        let mut crc = Crc32::new();
+        crc.update_u8(self.header.ID1);
+        crc.update_u8(self.header.ID2);
+        let mut decoder = HeaderDecoder {
+            input: ChecksumSource::new(self.input.as_mut(), Box::new(crc)),
+            header: mem::take(&mut self.header)
+        };
+        decoder.decode()?;
+        self.header = decoder.header;
+        Ok(())
+    }
+}
+
+pub struct GzipEncoder;
+
+impl GzipEncoder {
+    // A single member with no optional fields, mtime and OS unknown. The
+    // input is compressed a window at a time, each part with the previous
+    // window as history.
+    pub fn encode(input: Box<ByteSource>, output: Box<ByteSink>)
+        -> GzipResult<()> {
+
+        let window_size = DeflateFormat::Deflate.window_size();
+        let mut input = input;
+        let mut output = output;
+        output.put_data(&[31, 139, 8, 0, 0, 0, 0, 0, 0, 255])?;
+        let mut bits = BitSinkAdapter::new(output.as_mut());
+        let mut crc = Crc32::new();
+        let mut size : u64 = 0;
+        let mut window = vec![];
+        let mut data = input.get_data(window_size)?;
+        loop {
+            let next = input.get_data(window_size)?;
+            crc.update(&data);
+            size += data.len() as u64;
+            let history = window.len();
+            window.extend_from_slice(&data);
+            DeflateEncoder::new(&mut bits)
+                .encode_part(&window, history, next.is_empty())?;
+            if next.is_empty() {
+                break;
+            }
+            window.drain(..window.len().saturating_sub(window_size));
+            data = next;
+        }
+        verbose!(1, "CRC32: {:08x}, {} -> {} bytes", crc.value(), size,
+                 bits.bit_position().div_ceil(8) + 18);
+        bits.put_data(&crc.value().to_le_bytes())?;
+        bits.put_data(&(size as u32).to_le_bytes())
+    }
+}
+
+impl<'a> HeaderDecoder<'a> {
+    fn decode(&mut self) -> GzipResult<()> {
+        if self.header.ID1 != 31 || self.header.ID2 != 139 {
+            return Err(GzipError::NotAGzipFile);
+        }
+
+        self.header.CM = self.input.get_u8()?;
+        if self.header.CM != 8 {
+            return Err(GzipError::NotDeflate);
+        }
+
+        self.header.FLG = GzipFlags(self.input.get_u8()?);
+        verbose!(1, "File type is {}",
+            if self.header.FLG.contains(GzipFlags::FTEXT) {"ASCII"}
+            else {"Binary"});
+        if self.header.FLG.has_reserved() {
+            return Err(GzipError::ReservedFlagsNotSupported);
+        }
+
+        self.header.MTIME = self.input.get_u32()?;
+        if self.header.MTIME > 0 {
+            let timespec = time::Timespec::new(self.header.MTIME as i64, 0);
+            let tm = time::at_utc(timespec);
+            if let Ok(date) = time::strftime("%F %T", &tm) {
+                verbose!(1, "Date: {}", date);
+            }
+        }
+
+        self.header.XFL = GzipExtraFlags::from_u8(self.input.get_u8()?);
+
+        self.header.OS = GzipOs::from_u8(self.input.get_u8()?);
+        verbose!(1, "Operating System: {}", self.header.OS.name());
+
+        if self.header.FLG.contains(GzipFlags::FEXTRA) {
+            self.header.extra = Some(self.decode_extra()?);
+        }
+
+        if self.header.FLG.contains(GzipFlags::FNAME) {
+            self.header.original_name = self.decode_string()?;
+            if let Some(ref name) = self.header.original_name {
+                verbose!(1, "Original filename: {}", name);
+            }
+        }
+
+        if self.header.FLG.contains(GzipFlags::FCOMMENT) {
+            self.header.comment = self.decode_string()?;
+            if let Some(ref comment) = self.header.comment {
+                verbose!(1, "Comment: {}", comment);
+            }
+        }
+
+        if self.header.FLG.contains(GzipFlags::FHCRC) {
+            let crc16 = self.input.checksum() & 0xFFFF;
+            if self.input.get_u16()? as u32 != crc16 {
+                return Err(GzipError::HeaderCRC16Mismatch);
+            }
+            verbose!(1, "Header CRC16: {:04x}", crc16);
+        }
+        Ok(())
+    }
+
+    fn decode_string(&mut self) -> GzipResult<Option<String>> {
+        let mut iso_8859_1 : Vec<u8> = vec![];
+        loop {
+            let c = self.input.get_u8()?;
+            if c == 0 {
+                break;
+            }
+            iso_8859_1.push(c);
+        }
+        Ok(ISO_8859_1.decode(&iso_8859_1, DecoderTrap::Strict).ok())
+    }
+
+    fn decode_extra(&mut self) -> GzipResult<GzipExtra> {
+        let mut extra = GzipExtra {
+            XLEN: self.input.get_u16()?,
+            subfields: vec![]
+        };
+        let mut left = extra.XLEN as usize;
+        while left > 0 {
+            if left < 4 {
+                return Err(GzipError::InvalidExtraField);
+            }
+            let mut subfield = GzipSubfield {
+                SI1: self.input.get_u8()?,
+                SI2: self.input.get_u8()?,
+                data: vec![]
+            };
+            let length = self.input.get_u16()? as usize;
+            left -= 4;
+            if length > left {
+                return Err(GzipError::InvalidExtraField);
+            }
+            for _ in 0..length {
+                subfield.data.push(self.input.get_u8()?);
+            }
+            left -= length;
+            verbose!(1, "Extra subfield {}{}, {} bytes",
+                     subfield.SI1 as char, subfield.SI2 as char, length);
+            extra.subfields.push(subfield);
+        }
+        Ok(extra)
+    }
+}
+
+#[test]
+fn safe_name_rejects_paths() {
+    let mut header = GzipHeader::default();
+    assert!(header.safe_name().unwrap().is_none());
+    for name in &["file.txt", "..file", "file..txt"] {
+        header.original_name = Some(name.to_string());
+        assert!(header.safe_name().unwrap() == Some(*name));
+    }
+    for name in &["", ".", "..", "../file", "/etc/passwd", "dir/file",
+                  "dir\\file", "file/"] {
+        header.original_name = Some(name.to_string());
+        assert!(header.safe_name().is_err());
+    }
+}
+
+#[test]
+fn header_fields_are_typed() {
+    let flags = GzipFlags(GzipFlags::FNAME.0 | GzipFlags::FHCRC.0);
+    assert!(flags.names() == vec!["FHCRC", "FNAME"]);
+    assert!(!flags.contains(GzipFlags::FTEXT));
+    assert!(GzipFlags(0x20).has_reserved());
+    assert!(GzipOs::from_u8(3) == GzipOs::Unix);
+    assert!(GzipOs::from_u8(42) == GzipOs::Unknown(42));
+    assert!(GzipExtraFlags::from_u8(2) == GzipExtraFlags::Slowest);
+}
@@ -0,0 +1,321 @@
+use errors::{GzipResult, GzipError};
+use sources::bitsource::BitSource;
+use sources::bytesource::ByteSource;
+use sources::msbadapter::MsbAdapter;
+use blocks::huffman::{Huffman, HuffmanNode};
+use checksums::crc16::Crc16;
+use checksums::crc32::Crc32;
+use buffers::outputbuffer::OutputBuffer;
+use context::VERBOSE;
+
+const DICBIT : u8 = 13;
+const THRESHOLD : u32 = 3;
+const NC : usize = 256 + 256 + 2 - THRESHOLD as usize;
+const NT : usize = 19;
+const NP : usize = DICBIT as usize + 1;
+const CBIT : u8 = 9;
+const TBIT : u8 = 5;
+const PBIT : u8 = 4;
+const MAX_CODE_LENGTH : u32 = 16;
+
+#[derive(Debug)]
+pub struct LzhHeader {
+    pub method: Vec<u8>,
+    pub compressed_size: u64,
+    pub original_size: u64,
+    pub name: String,
+    pub crc16: u16,
+    pub level: u8
+}
+
+fn read_bytes(input: &mut ByteSource, size: usize) -> GzipResult<Vec<u8>> {
+    let mut data = Vec::with_capacity(size);
+    for _ in 0..size {
+        data.push(input.get_u8()?);
+    }
+    Ok(data)
+}
+
+fn little_endian(data: &[u8]) -> u64 {
+    data.iter().rev().fold(0, |value, &byte| (value << 8) | byte as u64)
+}
+
+impl LzhHeader {
+    // Levels 0 and 1 store the header size and an 8-bit checksum in the
+    // first two bytes, level 2 stores the total header size instead.
+    pub fn read(first: u8, second: u8, input: &mut ByteSource)
+        -> GzipResult<Self> {
+
+        let fixed = read_bytes(input, 19)?;
+        let field = |start: usize, size: usize| {
+            little_endian(&fixed[start - 2..start - 2 + size])
+        };
+        let mut header = LzhHeader {
+            method: fixed[0..5].to_vec(),
+            compressed_size: field(7, 4),
+            original_size: field(11, 4),
+            name: String::new(),
+            crc16: 0,
+            level: fixed[18]
+        };
+        let next = match header.level {
+            0 | 1 => {
+                let size = first as usize;
+                if size < fixed.len() + 3 {
+                    return Err(GzipError::InvalidLzhHeader);
+                }
+                let rest = read_bytes(input, size - fixed.len())?;
+                let sum = fixed.iter().chain(rest.iter())
+                    .fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+                let length = rest[0] as usize;
+                if sum != second || rest.len() < length + 3 {
+                    return Err(GzipError::InvalidLzhHeader);
+                }
+                header.name = String::from_utf8_lossy(&rest[1..length + 1])
+                    .into_owned();
+                header.crc16 = little_endian(&rest[length + 1..length + 3])
+                    as u16;
+                if header.level == 0 {
+                    return Ok(header);
+                }
+                if rest.len() < length + 6 {
+                    return Err(GzipError::InvalidLzhHeader);
+                }
+                little_endian(&rest[rest.len() - 2..]) as usize
+            },
+            2 => {
+                let rest = read_bytes(input, 5)?;
+                header.crc16 = little_endian(&rest[0..2]) as u16;
+                little_endian(&rest[3..5]) as usize
+            },
+            _ => return Err(GzipError::InvalidLzhHeader)
+        };
+        let extended = header.read_extended(input, next)?;
+        match header.level {
+            // Level 1 counts the extended headers as compressed data.
+            1 => {
+                header.compressed_size = header.compressed_size
+                    .checked_sub(extended as u64)
+                    .ok_or(GzipError::InvalidLzhHeader)?;
+            },
+            _ => {
+                let total = (first as usize) | (second as usize) << 8;
+                let size = 2 + fixed.len() + 5 + extended;
+                if total < size {
+                    return Err(GzipError::InvalidLzhHeader);
+                }
+                read_bytes(input, total - size)?;
+            }
+        }
+        Ok(header)
+    }
+
+    // Each extended header is a type, its data and the size of the next.
+    fn read_extended(&mut self, input: &mut ByteSource, next: usize)
+        -> GzipResult<usize> {
+
+        let mut next = next;
+        let mut total = 0;
+        while next != 0 {
+            if next < 3 {
+                return Err(GzipError::InvalidLzhHeader);
+            }
+            let extended = read_bytes(input, next)?;
+            verbose!(2, "LZH extended header {:02x}", extended[0]);
+            if extended[0] == 1 {
+                self.name = String::from_utf8_lossy(&extended[1..next - 2])
+                    .into_owned();
+            }
+            total += next;
+            next = little_endian(&extended[next - 2..]) as usize;
+        }
+        Ok(total)
+    }
+}
+
+pub struct LzhDecoder<'a> {
+    input: &'a mut BitSource,
+    output: &'a mut OutputBuffer,
+    block_size: u32,
+    literals: Huffman,
+    positions: Huffman
+}
+
+impl<'a> LzhDecoder<'a> {
+    pub fn decode(input: Box<ByteSource>, output: Box<OutputBuffer>)
+        -> GzipResult<()> {
+
+        let mut input = input;
+        let mut output = output;
+        let first = input.get_u8()?;
+        let second = input.get_u8()?;
+        if first == 31 && second == 160 {
+            // gzip reads this as a bare -lh5- stream, ended by an empty
+            // block or by the end of the file.
+            output.reset(Box::new(Crc32::new()), 1 << DICBIT)?;
+            Self::decode_stream(input.as_mut(), output.as_mut(), None)?;
+            let digest = output.digest()?;
+            verbose!(1, "Decoded {} bytes, CRC32: {:08x}",
+                     digest.size, digest.checksum);
+            return Ok(());
+        }
+        let header = LzhHeader::read(first, second, input.as_mut())?;
+        verbose!(1, "LZH level {} {}, method {}, {} -> {} bytes",
+                 header.level, header.name,
+                 String::from_utf8_lossy(&header.method),
+                 header.compressed_size, header.original_size);
+        output.reset(Box::new(Crc16::new()), 1 << DICBIT)?;
+        match &header.method[..] {
+            b"-lh5-" => Self::decode_stream(
+                input.as_mut(), output.as_mut(), Some(header.original_size))?,
+            b"-lh0-" => {
+                for _ in 0..header.compressed_size {
+                    output.put_u8(input.get_u8()?)?;
+                }
+            },
+            _ => return Err(GzipError::LzhMethodNotSupported)
+        }
+        let digest = output.digest()?;
+        verbose!(1, "CRC16: {:04x}", digest.checksum);
+        if digest.size != header.original_size {
+            return Err(GzipError::LzhSizeMismatch);
+        }
+        if digest.checksum != header.crc16 as u32 {
+            return Err(GzipError::LzhCRC16Mismatch);
+        }
+        Ok(())
+    }
+
+    fn decode_stream(input: &mut ByteSource, output: &mut OutputBuffer,
+                     size: Option<u64>) -> GzipResult<()> {
+
+        let mut bits = MsbAdapter::new(input);
+        let mut decoder = LzhDecoder {
+            input: &mut bits,
+            output,
+            block_size: 0,
+            literals: HuffmanNode::Code(0),
+            positions: HuffmanNode::Code(0)
+        };
+        decoder.decode_codes(size)
+    }
+
+    fn decode_codes(&mut self, size: Option<u64>) -> GzipResult<()> {
+        let mut decoded = 0;
+        while size.is_none_or(|size| decoded < size) {
+            if self.block_size == 0 && !self.read_block(size.is_none())? {
+                break;
+            }
+            self.block_size -= 1;
+            let code = Huffman::get_code(&self.literals, self.input)?;
+            if code < 256 {
+                self.output.put_u8(code as u8)?;
+                decoded += 1;
+            } else {
+                let length = code + THRESHOLD - 256;
+                let distance = self.get_position()? + 1;
+                self.output.copy_window(distance, length)?;
+                verbose!(2, "window {} {}", length, distance);
+                decoded += length as u64;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_block(&mut self, stream: bool) -> GzipResult<bool> {
+        self.block_size = match self.input.get_bits(16) {
+            Ok(size) => size,
+            Err(GzipError::TruncatedFile) if stream => 0,
+            Err(error) => return Err(error)
+        };
+        if self.block_size == 0 {
+            return Ok(false);
+        }
+        verbose!(1, "LZH block with {} codes", self.block_size);
+        let lengths = self.read_lengths(NT, TBIT, Some(3))?;
+        self.literals = self.read_literal_lengths(&lengths)?;
+        self.positions = self.read_lengths(NP, PBIT, None)?;
+        Ok(true)
+    }
+
+    // An empty table means that every code is the single symbol that
+    // follows, using no bits at all.
+    fn read_single(&mut self, size: usize, bits: u8) -> GzipResult<Huffman> {
+        let code = self.input.get_bits(bits)? as usize;
+        if code >= size {
+            return Err(GzipError::InvalidLzhStream);
+        }
+        Ok(HuffmanNode::Code(code as u16))
+    }
+
+    // Lengths up to 6 take three bits, longer ones continue in unary. The
+    // literal length table can skip up to three zeros after the third.
+    fn read_lengths(&mut self, size: usize, bits: u8, special: Option<usize>)
+        -> GzipResult<Huffman> {
+
+        let count = self.input.get_bits(bits)? as usize;
+        if count == 0 {
+            return self.read_single(size, bits);
+        }
+        let mut lengths = vec![];
+        while lengths.len() < count {
+            let mut length = self.input.get_bits(3)?;
+            if length == 7 {
+                while self.input.get_bit()? == 1 {
+                    length += 1;
+                    if length > MAX_CODE_LENGTH {
+                        return Err(GzipError::InvalidLzhStream);
+                    }
+                }
+            }
+            lengths.push(length as u8);
+            if special == Some(lengths.len()) {
+                let zeros = self.input.get_bits(2)? as usize;
+                lengths.append(&mut vec![0; zeros]);
+            }
+        }
+        Self::build(lengths, size)
+    }
+
+    fn read_literal_lengths(&mut self, code_huffman: &Huffman)
+        -> GzipResult<Huffman> {
+
+        let count = self.input.get_bits(CBIT)? as usize;
+        if count == 0 {
+            return self.read_single(NC, CBIT);
+        }
+        let mut lengths = vec![];
+        while lengths.len() < count {
+            match Huffman::get_code(code_huffman, self.input)? {
+                0 => lengths.push(0),
+                1 => {
+                    let zeros = 3 + self.input.get_bits(4)? as usize;
+                    lengths.append(&mut vec![0; zeros]);
+                },
+                2 => {
+                    let zeros = 20 + self.input.get_bits(CBIT)? as usize;
+                    lengths.append(&mut vec![0; zeros]);
+                },
+                code => lengths.push(code as u8 - 2)
+            }
+        }
+        Self::build(lengths, NC)
+    }
+
+    fn build(lengths: Vec<u8>, size: usize) -> GzipResult<Huffman> {
+        if lengths.len() > size || lengths.iter().all(|&length| length == 0) {
+            return Err(GzipError::InvalidLzhStream);
+        }
+        let mut lengths = lengths;
+        lengths.resize(size, 0);
+        Huffman::build(lengths)
+    }
+
+    fn get_position(&mut self) -> GzipResult<u32> {
+        let bits = Huffman::get_code(&self.positions, self.input)?;
+        if bits == 0 {
+            return Ok(0);
+        }
+        Ok((1 << (bits - 1)) + self.input.get_bits(bits as u8 - 1)?)
+    }
+}
@@ -0,0 +1,10 @@
+pub mod gzip;
+pub mod zlib;
+pub mod raw;
+pub mod detect;
+pub mod zip;
+pub mod bgzf;
+pub mod dictzip;
+pub mod compress;
+pub mod pack;
+pub mod lzh;
@@ -0,0 +1,110 @@
+use errors::{GzipResult, GzipError};
+use sources::bitsource::BitSource;
+use sources::bytesource::ByteSource;
+use sources::msbadapter::MsbAdapter;
+use blocks::window::DeflateFormat;
+use checksums::crc32::Crc32;
+use buffers::outputbuffer::OutputBuffer;
+use context::VERBOSE;
+
+const MAX_BIT_LENGTH : usize = 24;
+const MAX_LEAVES : usize = 257;
+
+// Each level of the tree holds the internal nodes first and then the leaves,
+// so a code is a leaf once its value reaches the number of parents.
+struct PackTree {
+    max_len: usize,
+    parents: Vec<u32>,
+    literals: Vec<Vec<u8>>
+}
+
+pub struct PackDecoder<'a> {
+    input: &'a mut BitSource,
+    output: &'a mut OutputBuffer
+}
+
+impl<'a> PackDecoder<'a> {
+    pub fn decode(input: Box<ByteSource>, output: Box<OutputBuffer>)
+        -> GzipResult<()> {
+
+        let mut input = input;
+        let mut output = output;
+        if input.get_u8()? != 31 || input.get_u8()? != 30 {
+            return Err(GzipError::InvalidPackHeader);
+        }
+        let mut size = 0u32;
+        for _ in 0..4 {
+            size = (size << 8) | input.get_u8()? as u32;
+        }
+        let tree = Self::read_tree(input.as_mut())?;
+        verbose!(1, "Pack size: {}, max bit length: {}", size, tree.max_len);
+        output.reset(Box::new(Crc32::new()),
+                     DeflateFormat::Deflate.window_size())?;
+        PackDecoder {
+            input: &mut MsbAdapter::new(input.as_mut()),
+            output: output.as_mut()
+        }.decode_codes(&tree)?;
+        let digest = output.digest()?;
+        verbose!(1, "CRC32: {:08x}", digest.checksum);
+        if digest.size != size as u64 {
+            return Err(GzipError::PackSizeMismatch);
+        }
+        Ok(())
+    }
+
+    // The leaf count of the last level is stored minus two, one for the
+    // end of block code, which is implicit, and one to fit 256 in a byte.
+    fn read_tree(input: &mut ByteSource) -> GzipResult<PackTree> {
+        let max_len = input.get_u8()? as usize;
+        if max_len == 0 || max_len > MAX_BIT_LENGTH {
+            return Err(GzipError::InvalidPackHeader);
+        }
+        let mut leaves = vec![0usize; max_len + 1];
+        for count in leaves.iter_mut().skip(1) {
+            *count = input.get_u8()? as usize;
+        }
+        leaves[max_len] += 2;
+        if leaves.iter().sum::<usize>() > MAX_LEAVES {
+            return Err(GzipError::InvalidPackHeader);
+        }
+        let mut parents = vec![1u32; max_len + 1];
+        let mut literals = vec![vec![]];
+        for len in 1..(max_len + 1) {
+            let nodes = 2 * parents[len - 1] as usize;
+            if leaves[len] > nodes {
+                return Err(GzipError::InvalidPackHeader);
+            }
+            parents[len] = (nodes - leaves[len]) as u32;
+            let count = if len == max_len {
+                leaves[len] - 1
+            } else {
+                leaves[len]
+            };
+            let mut level = vec![];
+            for _ in 0..count {
+                level.push(input.get_u8()?);
+            }
+            literals.push(level);
+        }
+        if parents[max_len] != 0 {
+            return Err(GzipError::InvalidPackHeader);
+        }
+        Ok(PackTree { max_len, parents, literals })
+    }
+
+    fn decode_codes(&mut self, tree: &PackTree) -> GzipResult<()> {
+        loop {
+            let mut len = 1;
+            let mut code = self.input.get_bit()?;
+            while code < tree.parents[len] {
+                code = (code << 1) | self.input.get_bit()?;
+                len += 1;
+            }
+            let index = (code - tree.parents[len]) as usize;
+            match tree.literals[len].get(index) {
+                Some(&literal) => self.output.put_u8(literal)?,
+                None => return Ok(())
+            }
+        }
+    }
+}
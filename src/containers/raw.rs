@@ -0,0 +1,37 @@
+use errors::GzipResult;
+use sources::bytesource::ByteSource;
+use sources::adaptermethod::AdapterMethod;
+use blocks::deflate::DeflateDecoder;
+use blocks::window::DeflateFormat;
+use checksums::crc32::Crc32;
+use buffers::outputbuffer::OutputBuffer;
+use context::VERBOSE;
+
+pub struct RawSummary {
+    pub compressed_bytes: u64,
+    pub compressed_bits: u64,
+    pub size: u64,
+}
+
+pub struct RawDecoder;
+
+impl RawDecoder {
+    pub fn decode(input: Box<ByteSource>, output: Box<OutputBuffer>,
+                  format: DeflateFormat, adapter: AdapterMethod)
+        -> GzipResult<RawSummary> {
+
+        let mut input = input;
+        let mut output = output;
+        let mut bits = adapter.wrap(input.as_mut());
+        output.reset(Box::new(Crc32::new()), format.window_size())?;
+        DeflateDecoder::new(bits.as_mut(), output.as_mut(), format).decode()?;
+        let digest = output.digest()?;
+        let summary = RawSummary {
+            compressed_bytes: bits.position(),
+            compressed_bits: bits.bit_position(),
+            size: digest.size
+        };
+        verbose!(1, "CRC32: {:08x}", digest.checksum);
+        Ok(summary)
+    }
+}
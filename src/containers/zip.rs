@@ -0,0 +1,382 @@
+use std::path::{Path, PathBuf, Component};
+use time;
+use encoding::{Encoding, DecoderTrap};
+use encoding::all::ISO_8859_1;
+use errors::{GzipResult, GzipError};
+use sources::bytesource::ByteSource;
+use sources::mapsource::MapSource;
+use sources::limitsource::LimitSource;
+use sources::adaptermethod::AdapterMethod;
+use blocks::deflate::DeflateDecoder;
+use blocks::window::DeflateFormat;
+use checksums::crc32::Crc32;
+use buffers::outputbuffer::OutputBuffer;
+use context::VERBOSE;
+
+const LOCAL_HEADER_SIGNATURE : u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE : u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE : u32 = 0x06054b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE : u32 = 0x06064b50;
+const ZIP64_LOCATOR_SIGNATURE : u32 = 0x07064b50;
+const ZIP64_LOCATOR_SIZE : u64 = 20;
+const ZIP64_EXTRA_ID : u16 = 1;
+
+// The end of central directory record is 22 bytes followed by a comment
+// of up to 65535 bytes.
+const END_OF_CENTRAL_DIRECTORY_SIZE : u64 = 22;
+const MAX_COMMENT_SIZE : u64 = 65535;
+
+const FLAG_ENCRYPTED : u16 = 1;
+const FLAG_UTF8 : u16 = 1 << 11;
+
+pub struct ZipEntry {
+    pub name: String,
+    pub flags: u16,
+    pub method: u16,
+    pub time: u16,
+    pub date: u16,
+    pub crc32: u32,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    pub local_offset: u64,
+}
+
+impl ZipEntry {
+    pub fn is_directory(&self) -> bool {
+        self.name.ends_with('/')
+    }
+
+    pub fn method_name(&self) -> &'static str {
+        match self.method {
+            0 => "stored",
+            8 => "deflate",
+            9 => "deflate64",
+            _ => "unknown"
+        }
+    }
+
+    // MS-DOS timestamps have no timezone, so they are read as UTC.
+    pub fn mtime(&self) -> u32 {
+        if self.date == 0 {
+            return 0;
+        }
+        let tm = time::Tm {
+            tm_sec: ((self.time & 0x1F) * 2) as i32,
+            tm_min: ((self.time >> 5) & 0x3F) as i32,
+            tm_hour: (self.time >> 11) as i32,
+            tm_mday: (self.date & 0x1F) as i32,
+            tm_mon: ((self.date >> 5) & 0xF) as i32 - 1,
+            tm_year: (self.date >> 9) as i32 + 80,
+            tm_wday: 0,
+            tm_yday: 0,
+            tm_isdst: 0,
+            tm_utcoff: 0,
+            tm_nsec: 0
+        };
+        tm.to_timespec().sec.max(0) as u32
+    }
+
+    // Entry names come from untrusted input, so only relative paths made
+    // of plain components are accepted.
+    pub fn safe_path(&self) -> GzipResult<PathBuf> {
+        let name = self.name.trim_end_matches('/');
+        let path = Path::new(name);
+        if name.is_empty() || name.contains('\\') ||
+            !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(GzipError::UnsafeEntryName);
+        }
+        Ok(path.to_path_buf())
+    }
+}
+
+pub struct ZipArchive {
+    input: MapSource,
+    pub entries: Vec<ZipEntry>,
+}
+
+impl ZipArchive {
+    pub fn open(name: &str) -> GzipResult<Self> {
+        let mut archive = ZipArchive {
+            input: MapSource::from_file(name)?,
+            entries: vec![]
+        };
+        let (offset, count) = archive.find_central_directory()?;
+        archive.input.seek(offset);
+        for _ in 0..count {
+            let entry = archive.decode_central_header()?;
+            verbose!(1, "Entry {}: {} bytes at offset {}",
+                     entry.name, entry.compressed_size, entry.local_offset);
+            archive.entries.push(entry);
+        }
+        Ok(archive)
+    }
+
+    pub fn find(&self, name: &str) -> GzipResult<usize> {
+        self.entries.iter().position(|entry| entry.name == name)
+            .ok_or(GzipError::ZipEntryNotFound)
+    }
+
+    pub fn decode(&mut self, index: usize, output: Box<OutputBuffer>,
+                  adapter: AdapterMethod) -> GzipResult<()> {
+
+        let mut output = output;
+        let (method, flags, offset, compressed_size) = {
+            let entry = &self.entries[index];
+            (entry.method, entry.flags, entry.local_offset,
+             entry.compressed_size)
+        };
+        if flags & FLAG_ENCRYPTED > 0 {
+            return Err(GzipError::ZipEncryptionNotSupported);
+        }
+        let format = match method {
+            0 | 8 => DeflateFormat::Deflate,
+            9 => DeflateFormat::Deflate64,
+            _ => return Err(GzipError::ZipMethodNotSupported)
+        };
+        self.input.seek(offset);
+        self.skip_local_header()?;
+        output.reset(Box::new(Crc32::new()), format.window_size())?;
+        if method == 0 {
+            for _ in 0..compressed_size {
+                let byte = self.input.get_u8()?;
+                output.put_u8(byte)?;
+            }
+        } else {
+            // The deflate stream must end exactly at the compressed size.
+            let end = self.input.position() + compressed_size;
+            let mut limited = LimitSource::new(&mut self.input,
+                                               compressed_size);
+            let mut bits = adapter.wrap(&mut limited);
+            match DeflateDecoder::new(bits.as_mut(), output.as_mut(), format)
+                .decode() {
+                Err(GzipError::TruncatedFile) =>
+                    return Err(GzipError::ZipCompressedSizeMismatch),
+                result => result?
+            }
+            if bits.position() != end {
+                return Err(GzipError::ZipCompressedSizeMismatch);
+            }
+        }
+        let digest = output.digest()?;
+        let entry = &self.entries[index];
+        verbose!(1, "CRC32: {:08x}, size: {}", entry.crc32, digest.size);
+        if digest.checksum != entry.crc32 {
+            return Err(GzipError::CRC32Mismatch);
+        }
+        if digest.size != entry.uncompressed_size {
+            return Err(GzipError::ZipSizeMismatch);
+        }
+        Ok(())
+    }
+
+    fn find_central_directory(&mut self) -> GzipResult<(u64, u64)> {
+        let size = self.input.size();
+        if size < END_OF_CENTRAL_DIRECTORY_SIZE {
+            return Err(GzipError::NotAZipFile);
+        }
+        let last = size - END_OF_CENTRAL_DIRECTORY_SIZE;
+        let first = last.saturating_sub(MAX_COMMENT_SIZE);
+        for pos in (first..last + 1).rev() {
+            self.input.seek(pos);
+            if self.input.get_u32()? != END_OF_CENTRAL_DIRECTORY_SIGNATURE {
+                continue;
+            }
+            let disk = self.input.get_u16()?;
+            let central_disk = self.input.get_u16()?;
+            let _disk_entries = self.input.get_u16()?;
+            let entries = self.input.get_u16()?;
+            let _central_size = self.input.get_u32()?;
+            let central_offset = self.input.get_u32()?;
+            let comment_size = self.input.get_u16()? as u64;
+            if pos + END_OF_CENTRAL_DIRECTORY_SIZE + comment_size != size {
+                continue;
+            }
+            if let Some(zip64) = self.find_zip64_central_directory(pos)? {
+                return Ok(zip64);
+            }
+            if disk != 0 || central_disk != 0 {
+                return Err(GzipError::ZipMultiDiskNotSupported);
+            }
+            verbose!(1, "Central directory at offset {}, {} entries",
+                     central_offset, entries);
+            return Ok((central_offset as u64, entries as u64));
+        }
+        Err(GzipError::NotAZipFile)
+    }
+
+    // The ZIP64 locator sits right before the end of central directory
+    // record and points to the ZIP64 record, which holds the full 64-bit
+    // values for the fields that were saturated in the original record.
+    fn find_zip64_central_directory(&mut self, end: u64)
+        -> GzipResult<Option<(u64, u64)>> {
+
+        if end < ZIP64_LOCATOR_SIZE {
+            return Ok(None);
+        }
+        self.input.seek(end - ZIP64_LOCATOR_SIZE);
+        if self.input.get_u32()? != ZIP64_LOCATOR_SIGNATURE {
+            return Ok(None);
+        }
+        let central_disk = self.input.get_u32()?;
+        let record_offset = self.input.get_u64()?;
+        let disks = self.input.get_u32()?;
+        if central_disk != 0 || disks > 1 {
+            return Err(GzipError::ZipMultiDiskNotSupported);
+        }
+        self.input.seek(record_offset);
+        if self.input.get_u32()? != ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE {
+            return Err(GzipError::InvalidZipHeader);
+        }
+        let _record_size = self.input.get_u64()?;
+        let _version_made_by = self.input.get_u16()?;
+        let _version_needed = self.input.get_u16()?;
+        let disk = self.input.get_u32()?;
+        let central_disk = self.input.get_u32()?;
+        let _disk_entries = self.input.get_u64()?;
+        let entries = self.input.get_u64()?;
+        let _central_size = self.input.get_u64()?;
+        let central_offset = self.input.get_u64()?;
+        if disk != 0 || central_disk != 0 {
+            return Err(GzipError::ZipMultiDiskNotSupported);
+        }
+        verbose!(1, "ZIP64 central directory at offset {}, {} entries",
+                 central_offset, entries);
+        Ok(Some((central_offset, entries)))
+    }
+
+    fn decode_central_header(&mut self) -> GzipResult<ZipEntry> {
+        if self.input.get_u32()? != CENTRAL_HEADER_SIGNATURE {
+            return Err(GzipError::InvalidZipHeader);
+        }
+        let _version_made_by = self.input.get_u16()?;
+        let _version_needed = self.input.get_u16()?;
+        let flags = self.input.get_u16()?;
+        let method = self.input.get_u16()?;
+        let time = self.input.get_u16()?;
+        let date = self.input.get_u16()?;
+        let crc32 = self.input.get_u32()?;
+        let compressed_size = self.input.get_u32()? as u64;
+        let uncompressed_size = self.input.get_u32()? as u64;
+        let name_size = self.input.get_u16()?;
+        let extra_size = self.input.get_u16()?;
+        let comment_size = self.input.get_u16()?;
+        let disk = self.input.get_u16()?;
+        let _internal_attributes = self.input.get_u16()?;
+        let _external_attributes = self.input.get_u32()?;
+        let local_offset = self.input.get_u32()? as u64;
+        let name = self.decode_name(name_size, flags)?;
+        let mut entry = ZipEntry {
+            name, flags, method, time, date, crc32,
+            compressed_size, uncompressed_size, local_offset
+        };
+        self.decode_extra(&mut entry, extra_size, disk)?;
+        self.skip(comment_size as u64)?;
+        Ok(entry)
+    }
+
+    fn decode_extra(&mut self, entry: &mut ZipEntry, size: u16, disk: u16)
+        -> GzipResult<()> {
+
+        let end = self.input.position() + size as u64;
+        while self.input.position() + 4 <= end {
+            let id = self.input.get_u16()?;
+            let length = self.input.get_u16()? as u64;
+            let next = self.input.position() + length;
+            if next > end {
+                return Err(GzipError::InvalidZipHeader);
+            }
+            if id == ZIP64_EXTRA_ID {
+                self.decode_zip64_extra(entry, length, disk)?;
+            }
+            self.input.seek(next);
+        }
+        self.input.seek(end);
+        Ok(())
+    }
+
+    // The ZIP64 extra field only carries the values whose 32-bit or
+    // 16-bit counterparts in the central header are saturated, in order.
+    fn decode_zip64_extra(&mut self, entry: &mut ZipEntry, length: u64,
+                          disk: u16) -> GzipResult<()> {
+
+        let end = self.input.position() + length;
+        let mut fields = vec![];
+        if entry.uncompressed_size == 0xFFFFFFFF {
+            fields.push(&mut entry.uncompressed_size);
+        }
+        if entry.compressed_size == 0xFFFFFFFF {
+            fields.push(&mut entry.compressed_size);
+        }
+        if entry.local_offset == 0xFFFFFFFF {
+            fields.push(&mut entry.local_offset);
+        }
+        for field in fields {
+            if self.input.position() + 8 > end {
+                return Err(GzipError::InvalidZipHeader);
+            }
+            *field = self.input.get_u64()?;
+        }
+        if disk == 0xFFFF {
+            if self.input.position() + 4 > end {
+                return Err(GzipError::InvalidZipHeader);
+            }
+            if self.input.get_u32()? != 0 {
+                return Err(GzipError::ZipMultiDiskNotSupported);
+            }
+        }
+        Ok(())
+    }
+
+    // Sizes and CRC are taken from the central directory, since the local
+    // header may defer them to a data descriptor.
+    fn skip_local_header(&mut self) -> GzipResult<()> {
+        if self.input.get_u32()? != LOCAL_HEADER_SIGNATURE {
+            return Err(GzipError::InvalidZipHeader);
+        }
+        self.skip(22)?;
+        let name_size = self.input.get_u16()? as u64;
+        let extra_size = self.input.get_u16()? as u64;
+        self.skip(name_size + extra_size)
+    }
+
+    // Names without the UTF-8 flag are CP437, which matches ISO-8859-1 on
+    // the printable ASCII range.
+    fn decode_name(&mut self, size: u16, flags: u16) -> GzipResult<String> {
+        let mut raw = vec![];
+        for _ in 0..size {
+            raw.push(self.input.get_u8()?);
+        }
+        let name = if flags & FLAG_UTF8 > 0 {
+            String::from_utf8(raw).ok()
+        } else {
+            ISO_8859_1.decode(&raw, DecoderTrap::Strict).ok()
+        };
+        name.ok_or(GzipError::InvalidZipHeader)
+    }
+
+    fn skip(&mut self, size: u64) -> GzipResult<()> {
+        let pos = self.input.position() + size;
+        if pos > self.input.size() {
+            return Err(GzipError::TruncatedFile);
+        }
+        self.input.seek(pos);
+        Ok(())
+    }
+}
+
+#[test]
+fn safe_path_rejects_escapes() {
+    let mut entry = ZipEntry {
+        name: String::new(), flags: 0, method: 0, time: 0, date: 0,
+        crc32: 0, compressed_size: 0, uncompressed_size: 0, local_offset: 0
+    };
+    for name in &["file.txt", "dir/file", "dir/", "a/b/c"] {
+        entry.name = name.to_string();
+        assert!(entry.safe_path().is_ok());
+    }
+    for name in &["", "/", "/etc/passwd", "../file", "dir/../../file",
+                  "./file", "dir\\file", "C:\\file"] {
+        entry.name = name.to_string();
+        assert!(entry.safe_path().is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use errors::{GzipResult, GzipError};
+use sources::bitsource::BitSource;
+use sources::bytesource::ByteSource;
+use sources::adaptermethod::AdapterMethod;
+use blocks::deflate::DeflateDecoder;
+use blocks::window::DeflateFormat;
+use checksums::checksum::Checksum;
+use checksums::adler32::Adler32;
+use buffers::outputbuffer::OutputBuffer;
+use context::VERBOSE;
+
+const FDICT : u8 = 32;
+
+#[derive(Default)]
+#[allow(non_snake_case)]
+pub struct ZlibHeader {
+    pub CMF: u8,
+    pub FLG: u8,
+    pub DICTID: Option<u32>,
+}
+
+impl ZlibHeader {
+    pub fn is_zlib(cmf: u8, flg: u8) -> bool {
+        let check = (cmf as u16) << 8 | flg as u16;
+        check.is_multiple_of(31) && cmf & 15 == 8 && cmf >> 4 <= 7
+    }
+
+    pub fn window_size(&self) -> usize {
+        1 << ((self.CMF >> 4) + 8)
+    }
+
+    fn translate_level(&self) -> &'static str {
+        match self.FLG >> 6 {
+            0 => "fastest",
+            1 => "fast",
+            2 => "default",
+            _ => "maximum"
+        }
+    }
+}
+
+pub struct ZlibDecoder<'a> {
+    input: Box<BitSource + 'a>,
+    output: Box<OutputBuffer>,
+    header: ZlibHeader
+}
+
+impl<'a> ZlibDecoder<'a> {
+    pub fn decode(input: Box<ByteSource>, output: Box<OutputBuffer>,
+                  dictionary: Option<Vec<u8>>, adapter: AdapterMethod)
+        -> GzipResult<ZlibHeader> {
+
+        let mut input = input;
+        let mut zlib = ZlibDecoder {
+            input: adapter.wrap(input.as_mut()),
+            output,
+            header: ZlibHeader::default()
+        };
+        zlib.decode_header(dictionary)?;
+        DeflateDecoder::new(zlib.input.as_mut(), zlib.output.as_mut(),
+                            DeflateFormat::Deflate).decode()?;
+        zlib.decode_trailer()?;
+        Ok(zlib.header)
+    }
+
+    fn decode_header(&mut self, dictionary: Option<Vec<u8>>)
+        -> GzipResult<()> {
+
+        self.header.CMF = self.input.get_u8()?;
+        self.header.FLG = self.input.get_u8()?;
+        let check = (self.header.CMF as u16) << 8 | self.header.FLG as u16;
+        if !check.is_multiple_of(31) {
+            return Err(GzipError::NotAZlibFile);
+        }
+        if self.header.CMF & 15 != 8 {
+            return Err(GzipError::NotDeflate);
+        }
+        if self.header.CMF >> 4 > 7 {
+            return Err(GzipError::InvalidZlibWindow);
+        }
+        verbose!(1, "Window size: {}", self.header.window_size());
+        verbose!(1, "Compression level: {}", self.header.translate_level());
+        self.output.reset(Box::new(Adler32::new()),
+                          self.header.window_size())?;
+
+        if self.header.FLG & FDICT > 0 {
+            let dictid = self.get_u32_be()?;
+            self.header.DICTID = Some(dictid);
+            verbose!(1, "Dictionary id: {:08x}", dictid);
+            let dictionary = dictionary.ok_or(GzipError::DictionaryRequired)?;
+            let mut adler = Adler32::new();
+            adler.update(&dictionary);
+            if adler.value() != dictid {
+                return Err(GzipError::DictionaryMismatch);
+            }
+            self.output.prime(&dictionary)?;
+        }
+        Ok(())
+    }
+
+    fn decode_trailer(&mut self) -> GzipResult<()> {
+        let adler32 = self.get_u32_be()?;
+        let digest = self.output.digest()?;
+        verbose!(1, "ADLER32: {:08x}, size: {}", adler32, digest.size);
+        if digest.checksum != adler32 {
+            return Err(GzipError::Adler32Mismatch);
+        }
+        Ok(())
+    }
+
+    fn get_u32_be(&mut self) -> GzipResult<u32> {
+        let mut ans : u32 = 0;
+        for _ in 0..4 {
+            ans = (ans << 8) | self.input.get_u8()? as u32;
+        }
+        Ok(ans)
+    }
+}
@@ -1,15 +1,13 @@
 pub static mut VERBOSE : u8 = 0;
-pub static mut SINK: u8 = 0;
-pub static mut SOURCE: u8 = 3;
-pub static mut BUFFER: u8 = 3;
-pub static mut ADAPTER: u8 = 1;
 
+#[macro_export]
 macro_rules! get_context {
-    ($var: expr) => {
+    ($var: expr) => {{
+        let var = &raw const $var;
         unsafe {
-            $var
+            *var
         }
-    }
+    }}
 }
 
 macro_rules! verbose {
@@ -14,19 +14,55 @@ pub enum GzipError {
     NotAGzipFile,
     TruncatedFile,
     NotDeflate,
-    FEXTRANotSupported,
-    FHCRCNotSupported,
-    FCOMMENTNotSupported,
     ReservedFlagsNotSupported,
     DeflateModeNotSupported,
     StoredHeaderFailure,
     InternalError,
     InvalidDeflateStream,
+    CRC32Mismatch,
+    ISIZEMismatch,
+    InvalidExtraField,
+    HeaderCRC16Mismatch,
+    NotAZlibFile,
+    InvalidZlibWindow,
+    DictionaryRequired,
+    DictionaryMismatch,
+    Adler32Mismatch,
+    UnknownContainer,
+    UnsafeOriginalName,
+    NoOriginalName,
+    OutputExists,
+    CantSetTime,
+    NotAZipFile,
+    InvalidZipHeader,
+    ZipMultiDiskNotSupported,
+    ZipMethodNotSupported,
+    ZipEncryptionNotSupported,
+    ZipSizeMismatch,
+    ZipCompressedSizeMismatch,
+    ZipEntryNotFound,
+    UnsafeEntryName,
+    NotABgzfFile,
+    BgzfBlockSizeMismatch,
+    InvalidVirtualOffset,
+    NotADictzipFile,
+    InvalidRange,
+    DictzipTooLarge,
+    InvalidCompressHeader,
+    InvalidLzwCode,
+    InvalidPackHeader,
+    PackSizeMismatch,
+    InvalidLzhHeader,
+    LzhMethodNotSupported,
+    InvalidLzhStream,
+    LzhSizeMismatch,
+    LzhCRC16Mismatch,
+    TrailingData(u64),
 }
 
 impl fmt::Display for GzipError {
     fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
-        use GzipError::*;
+        use errors::GzipError::*;
         let error = match *self {
             CantOpenFile => "Can't open file",
             CantReadFile => "Can't read from file",
@@ -35,14 +71,53 @@ impl fmt::Display for GzipError {
             NotAGzipFile => "Not a Gzip file",
             TruncatedFile => "Truncated file",
             NotDeflate => "Not a deflate stream",
-            FEXTRANotSupported => "Header flag FEXTRA not supported yet",
-            FHCRCNotSupported => "Header flag FHCRC not supported yet",
-            FCOMMENTNotSupported => "Header flag FCOMMENT not supported yet",
             ReservedFlagsNotSupported => "Reserved header flags not supported",
             DeflateModeNotSupported => "Reserved deflate mode not defined yet",
             StoredHeaderFailure => "Error in stored block header",
             InternalError => "Internal decoder error",
             InvalidDeflateStream => "Invalid deflate stream",
+            CRC32Mismatch => "CRC32 of decoded data does not match trailer",
+            ISIZEMismatch => "Size of decoded data does not match trailer",
+            InvalidExtraField => "Extra field lengths do not add up to XLEN",
+            HeaderCRC16Mismatch => "Header CRC16 mismatch, header is corrupted",
+            NotAZlibFile => "Not a Zlib file",
+            InvalidZlibWindow => "Zlib window size larger than 32K",
+            DictionaryRequired => "Stream requires a preset dictionary",
+            DictionaryMismatch => "Preset dictionary does not match DICTID",
+            Adler32Mismatch => "ADLER32 of decoded data does not match trailer",
+            UnknownContainer => "Unknown container format",
+            UnsafeOriginalName => "Original filename is not a safe file name",
+            NoOriginalName => "Can't find a name for the output file",
+            OutputExists => "Output file already exists, use --force",
+            CantSetTime => "Can't set modification time",
+            NotAZipFile => "Can't find ZIP end of central directory",
+            InvalidZipHeader => "Invalid ZIP header",
+            ZipMultiDiskNotSupported => "Multi-disk ZIP archives not supported",
+            ZipMethodNotSupported => "ZIP compression method not supported",
+            ZipEncryptionNotSupported => "Encrypted ZIP entries not supported",
+            ZipSizeMismatch => "Size of decoded data does not match ZIP entry",
+            ZipCompressedSizeMismatch =>
+                "Deflate stream does not end at ZIP compressed size",
+            ZipEntryNotFound => "Entry not found in ZIP archive",
+            UnsafeEntryName => "ZIP entry name is not a safe relative path",
+            NotABgzfFile => "Missing BGZF block size subfield",
+            BgzfBlockSizeMismatch =>
+                "Deflate stream does not end at BGZF block size",
+            InvalidVirtualOffset => "Virtual offset is outside the BGZF file",
+            NotADictzipFile => "Missing or invalid dictzip chunk table",
+            InvalidRange => "Range start is past the end of the file",
+            DictzipTooLarge => "Input too large for a dictzip chunk table",
+            InvalidCompressHeader => "Invalid compress header flags",
+            InvalidLzwCode => "Invalid code in LZW stream",
+            InvalidPackHeader => "Invalid pack header or Huffman tree",
+            PackSizeMismatch => "Decoded size does not match pack header",
+            InvalidLzhHeader => "Invalid LZH header",
+            LzhMethodNotSupported => "LZH compression method not supported",
+            InvalidLzhStream => "Invalid LZH stream",
+            LzhSizeMismatch => "Decoded size does not match LZH header",
+            LzhCRC16Mismatch => "Decoded CRC16 does not match LZH header",
+            TrailingData(offset) =>
+                return write!(f, "Trailing data at offset {}", offset),
         };
         write!(f, "{}", error)
     }
@@ -0,0 +1,16 @@
+// Gzip decompressor in Rust
+// Ricardo Bittencourt 2017
+
+extern crate time;
+extern crate encoding;
+extern crate memmap;
+
+#[macro_use]
+pub mod context;
+pub mod errors;
+pub mod sources;
+pub mod sinks;
+pub mod buffers;
+pub mod blocks;
+pub mod checksums;
+pub mod containers;
@@ -1,240 +1,423 @@
 // Gzip decompressor in Rust
 // Ricardo Bittencourt 2017
 
+#[macro_use]
+extern crate rgzip;
 extern crate time;
-extern crate encoding;
 extern crate getopts;
-extern crate memmap;
-
-#[macro_use]
-mod context;
-mod errors;
-mod sources;
-mod sinks;
-mod buffers;
-mod blocks;
 
 use std::env;
-use encoding::{Encoding, DecoderTrap};
-use encoding::all::ISO_8859_1;
-use errors::{GzipResult, GzipError};
-use sources::bitsource::BitSource;
-use sources::bytesource::ByteSource;
-use sources::vecsource::VecSource;
-use sources::bitadapter::BitAdapter;
-use sources::widesource::WideSource;
-use sources::wideadapter::WideAdapter;
-use sources::buffersource::BufferSource;
-use sources::vecbufsource::VecBufSource;
-use sources::mapsource::MapSource;
-use sinks::bytesink::ByteSinkProvider;
-use sinks::filesink::FileSink;
-use sinks::filebufsink::FileBufSink;
-use blocks::stored::BlockStored;
-use blocks::fixed::BlockFixed;
-use blocks::dynamic::BlockDynamic;
-use buffers::outputbuffer::OutputBuffer;
-use buffers::inmemory::InMemoryBuffer;
-use buffers::circular::CircularBuffer;
-use buffers::channel::ChannelBuffer;
-use buffers::copy::CopyBuffer;
+use std::process;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use rgzip::errors::{GzipResult, GzipError};
+use rgzip::sources::bytesource::ByteSource;
+use rgzip::sources::vecsource::VecSource;
+use rgzip::sources::widesource::WideSource;
+use rgzip::sources::buffersource::BufferSource;
+use rgzip::sources::vecbufsource::VecBufSource;
+use rgzip::sources::mapsource::MapSource;
+use rgzip::sources::adaptermethod::AdapterMethod;
+use rgzip::sinks::bytesink::ByteSinkProvider;
+use rgzip::sinks::filesink::FileSink;
+use rgzip::sinks::filebufsink::FileBufSink;
+use rgzip::sinks::nullsink::NullSink;
+use rgzip::sinks::textsink::TextSink;
+use rgzip::buffers::outputbuffer::OutputBuffer;
+use rgzip::buffers::buffermethod::BufferMethod;
+use rgzip::containers::gzip::{GzipDecoder, GzipEncoder, GzipHeader};
+use rgzip::containers::gzip::{GzipMember, GzipTrailer, TrailingPolicy};
+use rgzip::containers::zlib::ZlibDecoder;
+use rgzip::containers::raw::RawDecoder;
+use rgzip::containers::detect::{Container, ContainerDetector};
+use rgzip::containers::zip::ZipArchive;
+use rgzip::containers::bgzf::{BgzfReader, BgzfWriter};
+use rgzip::containers::dictzip::{DictzipReader, DictzipWriter};
+use rgzip::containers::compress::CompressDecoder;
+use rgzip::containers::pack::PackDecoder;
+use rgzip::containers::lzh::LzhDecoder;
+use rgzip::blocks::window::DeflateFormat;
+use rgzip::context::VERBOSE;
 use getopts::Options;
-use context::{VERBOSE, SINK, SOURCE, BUFFER, ADAPTER};
-
-#[allow(non_snake_case)]
-enum GzipHeaderFlags {
-    FTEXT = 1,
-    FHCRC = 2,
-    FEXTRA = 4,
-    FNAME = 8,
-    FCOMMENT = 16
+
+static mut SINK: u8 = 0;
+static mut SOURCE: u8 = 3;
+static mut BUFFER: u8 = 3;
+static mut ADAPTER: u8 = 1;
+static mut FORMAT: u8 = 3;
+static mut TRAILING: u8 = 0;
+static mut ASCII: bool = false;
+
+fn choose_sink(output: String) -> GzipResult<ByteSinkProvider> {
+    let sink = match get_context!(SINK) {
+        0 => FileSink::provider(output),
+        1 => FileBufSink::provider(output),
+        _ => return Err(GzipError::InternalError)
+    };
+    if get_context!(ASCII) {
+        Ok(TextSink::provider(sink))
+    } else {
+        Ok(sink)
+    }
 }
 
-#[derive(Default)]
-#[allow(non_snake_case)]
-struct GzipHeader {
-    ID1: u8,
-    ID2: u8,
-    CM: u8,
-    FLG: u8,
-    MTIME: u32,
-    XFL: u8,
-    OS: u8,
-    original_name: Option<String>,
+fn choose_buffer_method() -> GzipResult<BufferMethod> {
+    match get_context!(BUFFER) {
+        0 => Ok(BufferMethod::InMemory),
+        1 => Ok(BufferMethod::Circular),
+        2 => Ok(BufferMethod::Channel),
+        3 => Ok(BufferMethod::Copy),
+        _ => Err(GzipError::InternalError)
+    }
 }
 
-#[allow(non_snake_case)]
-struct BlockHeader {
-    BFINAL: u8,
-    BTYPE: u8,
+fn choose_buffer(sink: ByteSinkProvider) -> GzipResult<Box<OutputBuffer>> {
+    choose_buffer_method()?.create(sink)
 }
 
-struct GzipDecoder {
-    input: Box<ByteSource>,
-    output: Box<OutputBuffer>,
-    header: GzipHeader
+fn choose_adapter() -> GzipResult<AdapterMethod> {
+    match get_context!(ADAPTER) {
+        0 => Ok(AdapterMethod::Bit),
+        1 => Ok(AdapterMethod::Wide),
+        _ => Err(GzipError::InternalError)
+    }
 }
 
-impl GzipDecoder {
-    fn decode(input : Box<ByteSource>, output: Box<OutputBuffer>)
-        -> GzipResult<()> {
+fn choose_source(input: &str) -> GzipResult<Box<ByteSource>> {
+    match get_context!(SOURCE) {
+        0 => Ok(Box::new(VecSource::from_file(input)?)),
+        1 => Ok(Box::new(BufferSource::from_file(input)?)),
+        2 => Ok(Box::new(VecBufSource::from_file(input)?)),
+        3 => Ok(Box::new(WideSource::from_file(input)?)),
+        4 => Ok(Box::new(MapSource::from_file(input)?)),
+        _ => Err(GzipError::InternalError)
+    }
+}
 
-        let mut gzip = GzipDecoder {
-            input: input,
-            output: output,
-            header: GzipHeader::default()
-        };
-        gzip.decode_header()?;
-        gzip.decode_deflate()?;
-        Ok(())
+fn choose_container(source: Box<ByteSource>)
+    -> GzipResult<(Container, Box<ByteSource>)> {
+
+    match get_context!(FORMAT) {
+        0 => Ok((Container::Gzip, source)),
+        1 => Ok((Container::Zlib, source)),
+        2 => Ok((Container::Raw(DeflateFormat::Deflate), source)),
+        3 => ContainerDetector::detect(source, false),
+        4 => ContainerDetector::detect(source, true),
+        5 => Ok((Container::Raw(DeflateFormat::Deflate64), source)),
+        _ => Err(GzipError::InternalError)
     }
+}
 
-    fn decode_deflate(&mut self) -> GzipResult<()> {
-        let mut bits : Box<BitSource> = match get_context!(ADAPTER) {
-            0 => Box::new(BitAdapter::new(self.input.as_mut())),
-            1 => Box::new(WideAdapter::new(self.input.as_mut())),
-            _ => return Err(GzipError::InternalError)
-        };
-        for i in 1.. {
-            let header = BlockHeader{
-                BFINAL: bits.get_bit()? as u8,
-                BTYPE: bits.get_bits_rev(2)? as u8,
-            };
-            verbose!(1, "Block {} is final: {}", i, header.BFINAL > 0);
-            let mut output = self.output.as_mut();
-            try!(match header.BTYPE {
-                0 => BlockStored::new(bits.as_mut(), output).decode(),
-                1 => BlockFixed::new(bits.as_mut(), output).decode(),
-                2 => BlockDynamic::new(bits.as_mut(), output).decode(),
-                _ => Err(GzipError::DeflateModeNotSupported),
-            });
-            if header.BFINAL > 0 {
-                break;
-            }
-        }
-        Ok(())
+fn choose_policy() -> GzipResult<TrailingPolicy> {
+    match get_context!(TRAILING) {
+        0 => Ok(TrailingPolicy::Error),
+        1 => Ok(TrailingPolicy::Warn),
+        2 => Ok(TrailingPolicy::IgnoreZeros),
+        _ => Err(GzipError::InternalError)
     }
+}
 
-    fn decode_header(&mut self) -> GzipResult<()> {
-        use GzipHeaderFlags::*;
+fn decompress_gzip(source: Box<ByteSource>, buffer: Box<OutputBuffer>)
+    -> GzipResult<Vec<GzipMember>> {
 
-        self.header.ID1 = self.input.get_u8()?;
-        self.header.ID2 = self.input.get_u8()?;
-        if self.header.ID1 != 31 || self.header.ID2 != 139 {
-            return Err(GzipError::NotAGzipFile);
+    let policy = choose_policy()?;
+    let summary = GzipDecoder::decode(source, buffer, policy,
+                                      choose_adapter()?)?;
+    if let Some(offset) = summary.trailing {
+        if policy == TrailingPolicy::Warn {
+            println!("Warning: trailing garbage at offset {} ignored", offset);
         }
+    }
+    Ok(summary.members)
+}
 
-        self.header.CM = self.input.get_u8()?;
-        if self.header.CM != 8 {
-            return Err(GzipError::NotDeflate);
-        }
+fn read_dictionary(name: &str) -> GzipResult<Vec<u8>> {
+    use GzipError::*;
+    let mut data = vec![];
+    let mut file = File::open(name).or(Err(CantOpenFile))?;
+    file.read_to_end(&mut data).or(Err(CantReadFile))?;
+    Ok(data)
+}
 
-        self.header.FLG = self.input.get_u8()?;
-        verbose!(1, "File type is {}",
-            if self.header.FLG & (FTEXT as u8) > 0 {"ASCII"} else {"Binary"});
-        if self.header.FLG & (FEXTRA as u8) > 0 {
-            return Err(GzipError::FEXTRANotSupported);
-        }
-        if self.header.FLG & (FHCRC as u8) > 0 {
-            return Err(GzipError::FHCRCNotSupported);
-        }
-        if self.header.FLG & (FCOMMENT as u8) > 0 {
-            return Err(GzipError::FCOMMENTNotSupported);
+fn decompress(input: &str, sink: ByteSinkProvider, dictionary: Option<String>)
+    -> GzipResult<()> {
+
+    let dictionary = match dictionary {
+        Some(name) => Some(read_dictionary(&name)?),
+        None => None
+    };
+    let buffer = choose_buffer(sink)?;
+    let (container, source) = choose_container(choose_source(input)?)?;
+    match container {
+        Container::Gzip => { decompress_gzip(source, buffer)?; },
+        Container::Zlib => {
+            ZlibDecoder::decode(source, buffer, dictionary,
+                                choose_adapter()?)?;
+        },
+        Container::Raw(format) => {
+            let summary = RawDecoder::decode(source, buffer, format,
+                                             choose_adapter()?)?;
+            println!("Consumed {} bytes ({} bits), decoded {} bytes",
+                     summary.compressed_bytes, summary.compressed_bits,
+                     summary.size);
+        },
+        Container::Compress => {
+            CompressDecoder::decode(source, buffer, choose_adapter()?)?;
+        },
+        Container::Pack => PackDecoder::decode(source, buffer)?,
+        Container::Lzh => LzhDecoder::decode(source, buffer)?
+    }
+    Ok(())
+}
+
+fn format_mtime(mtime: u32) -> String {
+    let tm = time::at_utc(time::Timespec::new(mtime as i64, 0));
+    time::strftime("%b %d %H:%M", &tm).unwrap_or("-".to_string())
+}
+
+fn ratio(compressed: u64, uncompressed: u64) -> f64 {
+    if uncompressed == 0 {
+        0.0
+    } else {
+        100.0 * (1.0 - compressed as f64 / uncompressed as f64)
+    }
+}
+
+fn list_member(member: &GzipMember) {
+    let payload = member.size - member.header_size - 8;
+    let uncompressed = member.trailer.ISIZE as u64;
+    println!("{:<7} {:08x} {} {:>12} {:>12} {:>12} {:>5.1}% {}",
+             if member.header.CM == 8 { "deflate" } else { "unknown" },
+             member.trailer.CRC32, format_mtime(member.header.MTIME),
+             member.offset, member.size, uncompressed,
+             ratio(payload, uncompressed),
+             member.header.original_name.as_ref().map_or("", |s| s));
+}
+
+fn list(input: &str) -> GzipResult<()> {
+    let buffer = choose_buffer(NullSink::provider())?;
+    let source = choose_source(input)?;
+    let members = decompress_gzip(source, buffer)?;
+    println!("method  crc      date               offset   compressed \
+              uncompressed  ratio name");
+    for member in &members {
+        list_member(member);
+    }
+    if members.len() > 1 {
+        let compressed : u64 = members.iter().map(|m| m.size).sum();
+        let payload : u64 = members.iter()
+            .map(|m| m.size - m.header_size - 8).sum();
+        let uncompressed : u64 = members.iter()
+            .map(|m| m.trailer.ISIZE as u64).sum();
+        println!("{:>55} {:>12} {:>5.1}% (totals, {} members)",
+                 compressed, uncompressed, ratio(payload, uncompressed),
+                 members.len());
+    }
+    Ok(())
+}
+
+fn list_zip(input: &str) -> GzipResult<()> {
+    let archive = ZipArchive::open(input)?;
+    println!("method    crc      date           compressed uncompressed  \
+              ratio name");
+    for entry in &archive.entries {
+        println!("{:<9} {:08x} {} {:>12} {:>12} {:>5.1}% {}",
+                 entry.method_name(), entry.crc32, format_mtime(entry.mtime()),
+                 entry.compressed_size, entry.uncompressed_size,
+                 ratio(entry.compressed_size, entry.uncompressed_size),
+                 entry.name);
+    }
+    if archive.entries.len() > 1 {
+        let compressed : u64 = archive.entries.iter()
+            .map(|e| e.compressed_size).sum();
+        let uncompressed : u64 = archive.entries.iter()
+            .map(|e| e.uncompressed_size).sum();
+        println!("{:>44} {:>12} {:>5.1}% (totals, {} entries)",
+                 compressed, uncompressed, ratio(compressed, uncompressed),
+                 archive.entries.len());
+    }
+    Ok(())
+}
+
+fn test_zip(input: &str) -> GzipResult<()> {
+    let mut archive = ZipArchive::open(input)?;
+    for index in 0..archive.entries.len() {
+        archive.decode(index, choose_buffer(NullSink::provider())?,
+                       choose_adapter()?)?;
+    }
+    Ok(())
+}
+
+fn extract_zip(input: &str, directory: &str, entry: Option<String>)
+    -> GzipResult<()> {
+
+    let mut archive = ZipArchive::open(input)?;
+    let indices = match entry {
+        Some(name) => vec![archive.find(&name)?],
+        None => (0..archive.entries.len()).collect()
+    };
+    for index in indices {
+        let path = Path::new(directory)
+            .join(archive.entries[index].safe_path()?);
+        if archive.entries[index].is_directory() {
+            fs::create_dir_all(&path).or(Err(GzipError::CantCreateFile))?;
+            continue;
         }
-        if self.header.FLG >= 0x20 {
-            return Err(GzipError::ReservedFlagsNotSupported);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).or(Err(GzipError::CantCreateFile))?;
         }
+        println!("Extracting {}", path.display());
+        let sink = choose_sink(path.to_string_lossy().into_owned())?;
+        archive.decode(index, choose_buffer(sink)?, choose_adapter()?)?;
+        set_mtime(&path, archive.entries[index].mtime())?;
+    }
+    Ok(())
+}
 
-        self.header.MTIME = self.input.get_u32()?;
-        if self.header.MTIME > 0 {
-            let timespec = time::Timespec::new(self.header.MTIME as i64, 0);
-            let tm = time::at_utc(timespec);
-            if let Ok(date) = time::strftime("%F %T", &tm) {
-                verbose!(1, "Date: {}", date);
-            }
-        }
+fn compress_gzip(input: &str, output: String) -> GzipResult<()> {
+    let sink = choose_sink(output)?;
+    GzipEncoder::encode(choose_source(input)?, sink()?)
+}
 
-        self.header.XFL = self.input.get_u8()?;
+fn bgzf(input: &str, output: String, voffset: u64, length: Option<u64>)
+    -> GzipResult<()> {
 
-        self.header.OS = self.input.get_u8()?;
-        verbose!(1, "Operating System: {}", self.translate_os());
+    let mut reader = BgzfReader::open(input)?;
+    reader.decode(voffset, length, choose_sink(output)?,
+                  choose_buffer_method()?, choose_adapter()?)
+}
 
-        if self.header.FLG & (FNAME as u8) > 0 {
-            let mut iso_8859_1 : Vec<u8> = vec![];
-            loop {
-                let c = self.input.get_u8()?;
-                if c == 0 {
-                    break;
-                }
-                iso_8859_1.push(c);
-            }
-            let utf8 = ISO_8859_1.decode(&iso_8859_1, DecoderTrap::Strict);
-            if let Ok(name) = utf8 {
-                verbose!(1, "Original filename: {}", name);
-                self.header.original_name = Some(name);
-            }
-        }
-        Ok(())
-    }
-
-    fn translate_os(&self) -> &'static str {
-        match self.header.OS {
-			0 => "FAT filesystem (MS-DOS, OS/2, NT/Win32)",
-			1 => "Amiga",
-			2 => "VMS (or OpenVMS)",
-			3 => "Unix",
-			4 => "VM/CMS",
-			5 => "Atari TOS",
-			6 => "HPFS filesystem (OS/2, NT)",
-			7 => "Macintosh",
-			8 => "Z-System",
-			9 => "CP/M",
-			10 => "TOPS-20",
-			11 => "NTFS filesystem (NT)",
-			12 => "QDOS",
-			13 => "Acorn RISCOS",
-			_ => "unknown"
+fn compress_bgzf(input: &str, output: String) -> GzipResult<()> {
+    let sink = choose_sink(output)?;
+    BgzfWriter::encode(choose_source(input)?, sink()?)
+}
+
+fn dictzip(input: &str, output: String, start: u64, length: Option<u64>)
+    -> GzipResult<()> {
+
+    let mut reader = DictzipReader::open(input)?;
+    reader.decode(start, length, choose_sink(output)?,
+                  choose_buffer_method()?, choose_adapter()?)
+}
+
+fn compress_dictzip(input: &str, output: String) -> GzipResult<()> {
+    let sink = choose_sink(output)?;
+    DictzipWriter::encode(choose_source(input)?, sink()?)
+}
+
+fn probe(input: &str) -> GzipResult<()> {
+    let file = File::open(input).or(Err(GzipError::CantOpenFile))?;
+    let mut reader = BufReader::new(file);
+    let probe = GzipHeader::probe(&mut reader)?;
+    let header = &probe.header;
+    println!("Flags: {}", header.FLG.names().join(" "));
+    println!("Modification time: {}", format_mtime(header.MTIME));
+    println!("Extra flags: {}", header.XFL.name());
+    println!("Operating system: {}", header.OS.name());
+    if let Some(ref extra) = header.extra {
+        for subfield in &extra.subfields {
+            println!("Extra subfield: {}{}, {} bytes", subfield.SI1 as char,
+                     subfield.SI2 as char, subfield.data.len());
         }
     }
+    if let Some(ref name) = header.original_name {
+        println!("Original name: {}", name);
+    }
+    if let Some(ref comment) = header.comment {
+        println!("Comment: {}", comment);
+    }
+    println!("Header size: {}", probe.header_size);
+    if let Some(trailer) = GzipTrailer::find(&mut reader) {
+        println!("Last member CRC32: {:08x}, ISIZE: {}",
+                 trailer.CRC32, trailer.ISIZE);
+    }
+    Ok(())
 }
 
-fn choose_sink(output: String) -> GzipResult<ByteSinkProvider> {
-    match get_context!(SINK) {
-        0 => Ok(FileSink::provider(output)),
-        1 => Ok(FileBufSink::provider(output)),
-        _ => Err(GzipError::InternalError)
+fn test(inputs: &[String], dictionary: Option<String>, zip: bool) {
+    let mut failed = false;
+    for input in inputs {
+        let result = if zip {
+            test_zip(input)
+        } else {
+            decompress(input, NullSink::provider(), dictionary.clone())
+        };
+        match result {
+            Ok(_) => println!("{}: OK", input),
+            Err(error) => {
+                println!("{}: Error: {}", input, error);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
     }
 }
 
-fn choose_buffer(sink: ByteSinkProvider) -> GzipResult<Box<OutputBuffer>> {
-    match get_context!(BUFFER) {
-        0 => Ok(Box::new(InMemoryBuffer::new(sink)?)),
-        1 => Ok(Box::new(CircularBuffer::new(sink)?)),
-        2 => Ok(Box::new(ChannelBuffer::new(sink)?)),
-        3 => Ok(Box::new(CopyBuffer::new(sink)?)),
-        _ => Err(GzipError::InternalError)
+fn original_name(input: &str, directory: &str) -> GzipResult<(PathBuf, u32)> {
+    let mut source = choose_source(input)?;
+    let header = GzipHeader::read(source.as_mut())?;
+    let name = match header.safe_name()? {
+        Some(name) => name.to_string(),
+        None => Path::new(input)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".gz"))
+            .filter(|name| !name.is_empty())
+            .ok_or(GzipError::NoOriginalName)?
+            .to_string()
+    };
+    Ok((Path::new(directory).join(name), header.MTIME))
+}
+
+fn set_mtime(path: &Path, mtime: u32) -> GzipResult<()> {
+    if mtime == 0 {
+        return Ok(());
     }
+    let file = OpenOptions::new().write(true).open(path)
+        .or(Err(GzipError::CantOpenFile))?;
+    file.set_modified(UNIX_EPOCH + Duration::from_secs(mtime as u64))
+        .or(Err(GzipError::CantSetTime))
 }
 
-fn choose_source(input: &str) -> GzipResult<Box<ByteSource>> {
-    match get_context!(SOURCE) {
-        0 => Ok(Box::new(VecSource::from_file(input)?)),
-        1 => Ok(Box::new(BufferSource::from_file(input)?)),
-        2 => Ok(Box::new(VecBufSource::from_file(input)?)),
-        3 => Ok(Box::new(WideSource::from_file(input)?)),
-        4 => Ok(Box::new(MapSource::from_file(input)?)),
-        _ => Err(GzipError::InternalError)
+fn restore(input: &str, directory: &str, dictionary: Option<String>,
+           force: bool) -> GzipResult<()> {
+
+    let (output, mtime) = original_name(input, directory)?;
+    println!("Reading from {}, writing to {}", input, output.display());
+    if !force {
+        OpenOptions::new().write(true).create_new(true).open(&output)
+            .map_err(|error| match error.kind() {
+                ErrorKind::AlreadyExists => GzipError::OutputExists,
+                _ => GzipError::CantCreateFile
+            })?;
     }
+    let sink = choose_sink(output.to_string_lossy().into_owned())?;
+    decompress(input, sink, dictionary)?;
+    set_mtime(&output, mtime)
 }
 
-fn read_gzip(input: &str, output: String) -> GzipResult<()> {
-    let sink = choose_sink(output)?;
-    let buffer = choose_buffer(sink)?;
-    let source  = choose_source(input)?;
-    GzipDecoder::decode(source, buffer)
+fn check(result: GzipResult<()>) {
+    if let Err(error) = result {
+        println!("Error: {}", error);
+        process::exit(1);
+    }
 }
 
-const USAGE : &str = "Usage: rgzip [flags] input output";
+const USAGE : &str = "Usage: rgzip [flags] input output\n       \
+                      rgzip --list [flags] input\n       \
+                      rgzip --test [flags] input...\n       \
+                      rgzip --probe [flags] input\n       \
+                      rgzip --name [flags] input directory\n       \
+                      rgzip --zip [flags] input directory\n       \
+                      rgzip --voffset n [flags] input output\n       \
+                      rgzip --compress [flags] input output\n       \
+                      rgzip --bgzf [flags] input output\n       \
+                      rgzip --range n [flags] input output\n       \
+                      rgzip --dictzip [flags] input output";
 
 macro_rules! parse_int_argument {
     ($matches: expr, $arg: expr, $limit: expr, $msg: expr, $var: ident) => {
@@ -254,6 +437,19 @@ macro_rules! parse_int_argument {
     }
 }
 
+macro_rules! parse_u64_argument {
+    ($matches: expr, $arg: expr, $msg: expr) => {
+        match $matches.opt_str($arg).map(|n| n.parse::<u64>()) {
+            None => None,
+            Some(Ok(v)) => Some(v),
+            Some(Err(_)) => {
+                println!("{}", $msg);
+                return;
+            }
+        }
+    }
+}
+
 fn main() {
     println!("rgzip 0.1, by Ricardo Bittencourt 2017");
 
@@ -268,6 +464,38 @@ fn main() {
                 "Buffer method 0=InMemory 1=Circular \
                 2=Channel 3=Copy(def) 4=Map", "m")
         .optopt("a", "adapter", "Adapter method 0=Bit 1=Wide(def)", "m")
+        .optopt("f", "format",
+                "Container format 0=Gzip 1=Zlib 2=Raw 3=Auto(def) \
+                4=Auto with raw fallback 5=Raw Deflate64", "m")
+        .optopt("T", "trailing",
+                "Trailing data policy 0=Error(def) 1=Warn \
+                2=Ignore zero padding", "m")
+        .optopt("D", "dictionary", "Preset dictionary for zlib streams",
+                "file")
+        .optflag("A", "ascii",
+                 "Convert CRLF to LF in members marked as text")
+        .optflag("l", "list", "List members without writing output")
+        .optflag("t", "test", "Test integrity without writing output")
+        .optflag("p", "probe", "Show the header without decoding")
+        .optflag("N", "name",
+                 "Name the output after the original name and restore its \
+                 modification time, output is a directory")
+        .optflag("F", "force",
+                 "Overwrite an existing output file with --name")
+        .optflag("z", "zip",
+                 "Input is a ZIP archive, output is a directory")
+        .optopt("e", "entry", "Extract only this entry from a ZIP archive",
+                "name")
+        .optopt("o", "voffset",
+                "Decode a BGZF file starting at this virtual offset", "n")
+        .optopt("r", "range",
+                "Decode a dictzip file starting at this offset", "n")
+        .optopt("n", "length",
+                "Number of bytes to decode from the virtual offset or \
+                range start", "n")
+        .optflag("c", "compress", "Compress the input into a gzip file")
+        .optflag("B", "bgzf", "Compress the input into a BGZF file")
+        .optflag("Z", "dictzip", "Compress the input into a dictzip file")
         .optflag("h", "help", "Show help");
 
     let matches = match opts.parse(&args[1..]) {
@@ -287,16 +515,77 @@ fn main() {
     parse_int_argument!(matches, "s", 4, "Invalid source method", SOURCE);
     parse_int_argument!(matches, "b", 3, "Invalid buffer method", BUFFER);
     parse_int_argument!(matches, "a", 1, "Invalid adapter method", ADAPTER);
+    parse_int_argument!(matches, "f", 5, "Invalid container format", FORMAT);
+    parse_int_argument!(matches, "T", 2, "Invalid trailing policy", TRAILING);
+    if matches.opt_present("A") {
+        unsafe {
+            ASCII = true;
+        }
+    }
+    let zip = matches.opt_present("z");
+    if matches.opt_present("l") && !matches.free.is_empty() {
+        check(if zip { list_zip(&matches.free[0]) }
+              else { list(&matches.free[0]) });
+        return;
+    }
+    if matches.opt_present("p") && !matches.free.is_empty() {
+        check(probe(&matches.free[0]));
+        return;
+    }
+    if matches.opt_present("N") && matches.free.len() >= 2 {
+        check(restore(&matches.free[0], &matches.free[1],
+                      matches.opt_str("D"), matches.opt_present("F")));
+        println!("Finished");
+        return;
+    }
+    if matches.opt_present("t") && !matches.free.is_empty() {
+        test(&matches.free, matches.opt_str("D"), zip);
+        return;
+    }
     if matches.free.len() < 2 {
         println!("{}", USAGE);
         return;
     }
+    if matches.opt_present("c") {
+        check(compress_gzip(&matches.free[0], matches.free[1].clone()));
+        println!("Finished");
+        return;
+    }
+    if matches.opt_present("B") {
+        check(compress_bgzf(&matches.free[0], matches.free[1].clone()));
+        println!("Finished");
+        return;
+    }
+    if matches.opt_present("Z") {
+        check(compress_dictzip(&matches.free[0], matches.free[1].clone()));
+        println!("Finished");
+        return;
+    }
+    let length = parse_u64_argument!(matches, "n", "Invalid length");
+    if let Some(voffset) =
+        parse_u64_argument!(matches, "o", "Invalid virtual offset") {
+        check(bgzf(&matches.free[0], matches.free[1].clone(),
+                   voffset, length));
+        println!("Finished");
+        return;
+    }
+    if let Some(start) = parse_u64_argument!(matches, "r", "Invalid range") {
+        check(dictzip(&matches.free[0], matches.free[1].clone(),
+                      start, length));
+        println!("Finished");
+        return;
+    }
+    if zip {
+        check(extract_zip(&matches.free[0], &matches.free[1],
+                          matches.opt_str("e")));
+        println!("Finished");
+        return;
+    }
 
     let input = &matches.free[0];
     let output = matches.free[1].clone();
     println!("Reading from {}, writing to {}", input, output);
-    match read_gzip(input, output) {
-        Ok(_) => println!("Finished"),
-        Err(error) => println!("Error: {}", error)
-    }
+    check(choose_sink(output)
+          .and_then(|sink| decompress(input, sink, matches.opt_str("D"))));
+    println!("Finished");
 }
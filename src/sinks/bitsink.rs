@@ -0,0 +1,22 @@
+use errors::GzipResult;
+use sinks::bytesink::ByteSink;
+
+pub trait BitSink : ByteSink {
+    fn put_bit(&mut self, bit: u32) -> GzipResult<()>;
+
+    fn bit_position(&self) -> u64;
+
+    fn put_bits(&mut self, value: u32, size: u8) -> GzipResult<()> {
+        for i in (0..size).rev() {
+            self.put_bit((value >> i) & 1)?;
+        }
+        Ok(())
+    }
+
+    fn put_bits_rev(&mut self, value: u32, size: u8) -> GzipResult<()> {
+        for i in 0..size {
+            self.put_bit((value >> i) & 1)?;
+        }
+        Ok(())
+    }
+}
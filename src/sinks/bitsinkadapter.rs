@@ -0,0 +1,55 @@
+use errors::GzipResult;
+use sinks::bytesink::ByteSink;
+use sinks::bitsink::BitSink;
+
+pub struct BitSinkAdapter<'a> {
+    data: &'a mut ByteSink,
+    pos: u8,
+    cur: u8,
+    written: u64
+}
+
+impl<'a> BitSinkAdapter<'a> {
+    pub fn new(data: &'a mut ByteSink) -> Self {
+        BitSinkAdapter{ data, pos: 0, cur: 0, written: 0 }
+    }
+
+    // Writes any pending bits padded with zeros.
+    pub fn align(&mut self) -> GzipResult<()> {
+        if self.pos > 0 {
+            self.data.put_u8(self.cur)?;
+            self.written += 1;
+            self.pos = 0;
+            self.cur = 0;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> BitSink for BitSinkAdapter<'a> {
+    fn put_bit(&mut self, bit: u32) -> GzipResult<()> {
+        self.cur |= (bit as u8) << self.pos;
+        self.pos += 1;
+        if self.pos == 8 {
+            self.data.put_u8(self.cur)?;
+            self.written += 1;
+            self.pos = 0;
+            self.cur = 0;
+        }
+        Ok(())
+    }
+
+    fn bit_position(&self) -> u64 {
+        self.written * 8 + self.pos as u64
+    }
+}
+
+impl<'a> ByteSink for BitSinkAdapter<'a> {
+    // Whole bytes start at a byte boundary, so any pending bits are padded
+    // with zeros first.
+    fn put_u8(&mut self, data: u8) -> GzipResult<()> {
+        self.align()?;
+        self.written += 1;
+        self.data.put_u8(data)
+    }
+}
@@ -9,6 +9,11 @@ pub trait ByteSink {
         }
         Ok(())
     }
+
+    // Called at the start of each gzip member with its FTEXT flag.
+    fn set_text(&mut self, _text: bool) -> GzipResult<()> {
+        Ok(())
+    }
 }
 
 pub type ByteSinkProvider =
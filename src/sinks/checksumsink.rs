@@ -0,0 +1,47 @@
+use errors::GzipResult;
+use sinks::bytesink::ByteSink;
+use checksums::checksum::Checksum;
+
+pub struct StreamDigest {
+    pub checksum: u32,
+    pub size: u64
+}
+
+pub struct ChecksumSink {
+    output: Box<ByteSink>,
+    checksum: Box<Checksum>,
+    size: u64
+}
+
+impl ChecksumSink {
+    pub fn new(output: Box<ByteSink>, checksum: Box<Checksum>) -> Self {
+        ChecksumSink{ output, checksum, size: 0 }
+    }
+
+    pub fn reset(&mut self, checksum: Box<Checksum>) {
+        self.checksum = checksum;
+        self.size = 0;
+    }
+
+    pub fn digest(&self) -> StreamDigest {
+        StreamDigest{ checksum: self.checksum.value(), size: self.size }
+    }
+}
+
+impl ByteSink for ChecksumSink {
+    fn put_u8(&mut self, data: u8) -> GzipResult<()> {
+        self.checksum.update_u8(data);
+        self.size += 1;
+        self.output.put_u8(data)
+    }
+
+    fn put_data(&mut self, data: &[u8]) -> GzipResult<()> {
+        self.checksum.update(data);
+        self.size += data.len() as u64;
+        self.output.put_data(data)
+    }
+
+    fn set_text(&mut self, text: bool) -> GzipResult<()> {
+        self.output.set_text(text)
+    }
+}
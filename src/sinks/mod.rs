@@ -1,3 +1,10 @@
 pub mod bytesink;
 pub mod filesink;
 pub mod filebufsink;
+pub mod checksumsink;
+pub mod nullsink;
+pub mod rangesink;
+pub mod textsink;
+pub mod bitsink;
+pub mod bitsinkadapter;
+pub mod vecsink;
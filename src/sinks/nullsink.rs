@@ -0,0 +1,20 @@
+use errors::GzipResult;
+use sinks::bytesink::{ByteSink, ByteSinkProvider};
+
+pub struct NullSink;
+
+impl NullSink {
+    pub fn provider() -> ByteSinkProvider {
+        Box::new(|| Ok(Box::new(NullSink)))
+    }
+}
+
+impl ByteSink for NullSink {
+    fn put_u8(&mut self, _data: u8) -> GzipResult<()> {
+        Ok(())
+    }
+
+    fn put_data(&mut self, _data: &[u8]) -> GzipResult<()> {
+        Ok(())
+    }
+}
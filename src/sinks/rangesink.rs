@@ -0,0 +1,40 @@
+use errors::GzipResult;
+use sinks::bytesink::{ByteSink, ByteSinkProvider};
+
+pub struct RangeSink {
+    output: Box<ByteSink>,
+    skip: u64,
+    left: Option<u64>
+}
+
+impl RangeSink {
+    // Drops the first skip bytes, then forwards at most length bytes.
+    pub fn provider(sink: ByteSinkProvider, skip: u64, length: Option<u64>)
+        -> ByteSinkProvider {
+
+        Box::new(move || {
+            Ok(Box::new(RangeSink{ output: sink()?, skip, left: length }))
+        })
+    }
+}
+
+impl ByteSink for RangeSink {
+    fn put_u8(&mut self, data: u8) -> GzipResult<()> {
+        self.put_data(&[data])
+    }
+
+    fn put_data(&mut self, data: &[u8]) -> GzipResult<()> {
+        let skipped = self.skip.min(data.len() as u64);
+        self.skip -= skipped;
+        let mut data = &data[skipped as usize..];
+        if let Some(left) = self.left {
+            let size = left.min(data.len() as u64);
+            self.left = Some(left - size);
+            data = &data[..size as usize];
+        }
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.output.put_data(data)
+    }
+}
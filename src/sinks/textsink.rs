@@ -0,0 +1,65 @@
+use errors::GzipResult;
+use sinks::bytesink::{ByteSink, ByteSinkProvider};
+
+// Converts CRLF to LF while the current member is marked as text. A CR at
+// the end of a write is held until the next byte shows if it starts a CRLF.
+pub struct TextSink {
+    output: Box<ByteSink>,
+    text: bool,
+    pending_cr: bool
+}
+
+impl TextSink {
+    pub fn provider(sink: ByteSinkProvider) -> ByteSinkProvider {
+        Box::new(move || {
+            Ok(Box::new(TextSink{ output: sink()?, text: false,
+                                  pending_cr: false }))
+        })
+    }
+
+    fn flush(&mut self) -> GzipResult<()> {
+        if self.pending_cr {
+            self.pending_cr = false;
+            self.output.put_u8(b'\r')?;
+        }
+        Ok(())
+    }
+}
+
+impl ByteSink for TextSink {
+    fn put_u8(&mut self, data: u8) -> GzipResult<()> {
+        self.put_data(&[data])
+    }
+
+    fn put_data(&mut self, data: &[u8]) -> GzipResult<()> {
+        if !self.text {
+            return self.output.put_data(data);
+        }
+        let mut converted = Vec::with_capacity(data.len());
+        for &byte in data {
+            if self.pending_cr && byte != b'\n' {
+                converted.push(b'\r');
+            }
+            self.pending_cr = byte == b'\r';
+            if !self.pending_cr {
+                converted.push(byte);
+            }
+        }
+        if converted.is_empty() {
+            return Ok(());
+        }
+        self.output.put_data(&converted)
+    }
+
+    fn set_text(&mut self, text: bool) -> GzipResult<()> {
+        self.flush()?;
+        self.text = text;
+        Ok(())
+    }
+}
+
+impl Drop for TextSink {
+    fn drop(&mut self) {
+        self.flush().unwrap();
+    }
+}
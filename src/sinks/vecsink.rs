@@ -0,0 +1,16 @@
+use errors::GzipResult;
+use sinks::bytesink::ByteSink;
+
+// Collects the output in memory, for formats that need its size before
+// writing it.
+impl ByteSink for Vec<u8> {
+    fn put_u8(&mut self, data: u8) -> GzipResult<()> {
+        self.push(data);
+        Ok(())
+    }
+
+    fn put_data(&mut self, data: &[u8]) -> GzipResult<()> {
+        self.extend_from_slice(data);
+        Ok(())
+    }
+}
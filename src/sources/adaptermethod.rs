@@ -0,0 +1,20 @@
+use sources::bytesource::ByteSource;
+use sources::bitsource::BitSource;
+use sources::bitadapter::BitAdapter;
+use sources::wideadapter::WideAdapter;
+
+// Which adapter the decoders use to read deflate bits from a ByteSource.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AdapterMethod {
+    Bit,
+    Wide
+}
+
+impl AdapterMethod {
+    pub fn wrap<'a>(self, input: &'a mut ByteSource) -> Box<BitSource + 'a> {
+        match self {
+            AdapterMethod::Bit => Box::new(BitAdapter::new(input)),
+            AdapterMethod::Wide => Box::new(WideAdapter::new(input))
+        }
+    }
+}
@@ -25,6 +25,10 @@ impl<'a> BitSource for BitAdapter<'a> {
         self.pos -= 1;
         Ok(ans as u32)
     }
+
+    fn bit_position(&self) -> u64 {
+        self.data.position() * 8 - self.pos as u64
+    }
 }
 
 impl<'a> ByteSource for BitAdapter<'a> {
@@ -32,6 +36,10 @@ impl<'a> ByteSource for BitAdapter<'a> {
         self.pos = 0;
         self.data.get_u8()
     }
+
+    fn position(&self) -> u64 {
+        self.bit_position().div_ceil(8)
+    }
 }
 
 
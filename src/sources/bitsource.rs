@@ -4,6 +4,8 @@ use sources::bytesource::ByteSource;
 pub trait BitSource : ByteSource {
     fn get_bit(&mut self) -> GzipResult<u32>;
 
+    fn bit_position(&self) -> u64;
+
     fn get_bits(&mut self, size: u8) -> GzipResult<u32> {
         let mut ans : u32 = 0;
         for _ in 0..size {
@@ -5,23 +5,31 @@ use errors::{GzipResult, GzipError};
 use sources::bytesource::ByteSource;
 
 pub struct BufferSource {
-    file: Bytes<BufReader<File>>
+    file: Bytes<BufReader<File>>,
+    pos: u64
 }
 
 impl ByteSource for BufferSource {
     fn get_u8(&mut self) -> GzipResult<u8> {
         match self.file.next() {
-            Some(Ok(v)) => Ok(v),
+            Some(Ok(v)) => {
+                self.pos += 1;
+                Ok(v)
+            },
             _ => Err(GzipError::TruncatedFile)
         }
     }
+
+    fn position(&self) -> u64 {
+        self.pos
+    }
 }
 
 impl BufferSource {
     pub fn from_file(name: &str) -> GzipResult<Self> {
-        use GzipError::*;
+        use errors::GzipError::*;
         let file = File::open(name).or(Err(CantOpenFile))?;
-        Ok(BufferSource{ file: BufReader::new(file).bytes() })
+        Ok(BufferSource{ file: BufReader::new(file).bytes(), pos: 0 })
     }
 }
 
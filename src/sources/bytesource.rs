@@ -1,4 +1,4 @@
-use errors::GzipResult;
+use errors::{GzipResult, GzipError};
 
 macro_rules! get_variable {
     ($self: tt, $type : ty, $size : expr) => {{
@@ -13,6 +13,8 @@ macro_rules! get_variable {
 pub trait ByteSource {
     fn get_u8(&mut self) -> GzipResult<u8>;
 
+    fn position(&self) -> u64;
+
     fn get_u16(&mut self) -> GzipResult<u16> {
         get_variable!(self, u16, 2)
     }
@@ -24,6 +26,35 @@ pub trait ByteSource {
     fn get_u64(&mut self) -> GzipResult<u64> {
         get_variable!(self, u64, 8)
     }
+
+    // Reads up to limit bytes, fewer only when the input ends.
+    fn get_data(&mut self, limit: usize) -> GzipResult<Vec<u8>> {
+        let mut data = Vec::with_capacity(limit);
+        while data.len() < limit {
+            match self.get_u8() {
+                Ok(byte) => data.push(byte),
+                Err(GzipError::TruncatedFile) => break,
+                Err(error) => return Err(error)
+            }
+        }
+        Ok(data)
+    }
+
+    // Bytes that can be read right now without failing, zero when the
+    // source can't tell.
+    fn available(&self) -> u64 {
+        0
+    }
+
+    // Fast path for wide readers: returns None without consuming any
+    // input when 8 bytes are not readily available.
+    fn try_get_u64(&mut self) -> Option<u64> {
+        if self.available() >= 8 {
+            self.get_u64().ok()
+        } else {
+            None
+        }
+    }
 }
 
 
@@ -0,0 +1,30 @@
+use errors::GzipResult;
+use sources::bytesource::ByteSource;
+use checksums::checksum::Checksum;
+
+pub struct ChecksumSource<'a> {
+    data: &'a mut ByteSource,
+    checksum: Box<Checksum>
+}
+
+impl<'a> ChecksumSource<'a> {
+    pub fn new(data: &'a mut ByteSource, checksum: Box<Checksum>) -> Self {
+        ChecksumSource{ data, checksum }
+    }
+
+    pub fn checksum(&self) -> u32 {
+        self.checksum.value()
+    }
+}
+
+impl<'a> ByteSource for ChecksumSource<'a> {
+    fn get_u8(&mut self) -> GzipResult<u8> {
+        let ans = self.data.get_u8()?;
+        self.checksum.update_u8(ans);
+        Ok(ans)
+    }
+
+    fn position(&self) -> u64 {
+        self.data.position()
+    }
+}
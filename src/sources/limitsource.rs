@@ -0,0 +1,33 @@
+use errors::{GzipResult, GzipError};
+use sources::bytesource::ByteSource;
+
+// Reads at most limit bytes from the inner source, then fails as if the
+// file had ended there.
+pub struct LimitSource<'a> {
+    data: &'a mut ByteSource,
+    left: u64
+}
+
+impl<'a> LimitSource<'a> {
+    pub fn new(data: &'a mut ByteSource, limit: u64) -> Self {
+        LimitSource{ data, left: limit }
+    }
+}
+
+impl<'a> ByteSource for LimitSource<'a> {
+    fn get_u8(&mut self) -> GzipResult<u8> {
+        if self.left == 0 {
+            return Err(GzipError::TruncatedFile);
+        }
+        self.left -= 1;
+        self.data.get_u8()
+    }
+
+    fn position(&self) -> u64 {
+        self.data.position()
+    }
+
+    fn available(&self) -> u64 {
+        self.left.min(self.data.available())
+    }
+}
@@ -18,16 +18,32 @@ impl ByteSource for MapSource {
         self.pos += 1;
         ans
     }
+
+    fn available(&self) -> u64 {
+        self.file.len().saturating_sub(self.pos) as u64
+    }
+
+    fn position(&self) -> u64 {
+        self.pos.min(unsafe { self.file.as_slice() }.len()) as u64
+    }
 }
 
 impl MapSource {
     pub fn from_file(name: &str) -> GzipResult<Self> {
-        use GzipError::*;
+        use errors::GzipError::*;
 
         let file = Mmap::open_path(name, Protection::Read)
             .or(Err(CantOpenFile))?;
         Ok(MapSource{ file, pos: 0 })
     }
+
+    pub fn size(&self) -> u64 {
+        self.file.len() as u64
+    }
+
+    pub fn seek(&mut self, pos: u64) {
+        self.pos = pos as usize;
+    }
 }
 
 
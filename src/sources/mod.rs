@@ -7,3 +7,9 @@ pub mod bitadapter;
 pub mod wideadapter;
 pub mod widesource;
 pub mod mapsource;
+pub mod checksumsource;
+pub mod prefixsource;
+pub mod msbadapter;
+pub mod adaptermethod;
+pub mod readsource;
+pub mod limitsource;
@@ -0,0 +1,43 @@
+use errors::GzipResult;
+use sources::bytesource::ByteSource;
+use sources::bitsource::BitSource;
+
+// Reads bits starting from the high bit of each byte, as pack and LZH
+// write them, so get_bits returns their codes and values directly.
+pub struct MsbAdapter<'a> {
+    data: &'a mut ByteSource,
+    pos: u8,
+    cur: u8
+}
+
+impl<'a> MsbAdapter<'a> {
+    pub fn new(data: &'a mut ByteSource) -> Self {
+        MsbAdapter{ data, pos: 0, cur: 0 }
+    }
+}
+
+impl<'a> BitSource for MsbAdapter<'a> {
+    fn get_bit(&mut self) -> GzipResult<u32> {
+        if self.pos == 0 {
+            self.cur = self.data.get_u8()?;
+            self.pos = 8;
+        }
+        self.pos -= 1;
+        Ok((self.cur >> self.pos) as u32 & 1)
+    }
+
+    fn bit_position(&self) -> u64 {
+        self.data.position() * 8 - self.pos as u64
+    }
+}
+
+impl<'a> ByteSource for MsbAdapter<'a> {
+    fn get_u8(&mut self) -> GzipResult<u8> {
+        self.pos = 0;
+        self.data.get_u8()
+    }
+
+    fn position(&self) -> u64 {
+        self.bit_position().div_ceil(8)
+    }
+}
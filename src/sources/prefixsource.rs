@@ -0,0 +1,37 @@
+use errors::GzipResult;
+use sources::bytesource::ByteSource;
+
+pub struct PrefixSource {
+    prefix: Vec<u8>,
+    pos: usize,
+    data: Box<ByteSource>
+}
+
+impl PrefixSource {
+    pub fn new(prefix: Vec<u8>, data: Box<ByteSource>) -> Self {
+        PrefixSource{ prefix, pos: 0, data }
+    }
+}
+
+impl ByteSource for PrefixSource {
+    fn get_u8(&mut self) -> GzipResult<u8> {
+        if self.pos < self.prefix.len() {
+            self.pos += 1;
+            Ok(self.prefix[self.pos - 1])
+        } else {
+            self.data.get_u8()
+        }
+    }
+
+    fn try_get_u64(&mut self) -> Option<u64> {
+        if self.pos < self.prefix.len() {
+            None
+        } else {
+            self.data.try_get_u64()
+        }
+    }
+
+    fn position(&self) -> u64 {
+        self.data.position() - (self.prefix.len() - self.pos) as u64
+    }
+}
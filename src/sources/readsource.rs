@@ -0,0 +1,35 @@
+use std::io::{Read, ErrorKind};
+use errors::{GzipResult, GzipError};
+use sources::bytesource::ByteSource;
+
+// Reads from anything that implements Read, one byte at a time and never
+// past what was asked, so the reader should be buffered already.
+pub struct ReadSource<R: Read> {
+    data: R,
+    pos: u64
+}
+
+impl<R: Read> ReadSource<R> {
+    pub fn new(data: R) -> Self {
+        ReadSource{ data, pos: 0 }
+    }
+}
+
+impl<R: Read> ByteSource for ReadSource<R> {
+    fn get_u8(&mut self) -> GzipResult<u8> {
+        let mut byte = [0u8];
+        match self.data.read_exact(&mut byte) {
+            Ok(()) => {
+                self.pos += 1;
+                Ok(byte[0])
+            },
+            Err(ref error) if error.kind() == ErrorKind::UnexpectedEof =>
+                Err(GzipError::TruncatedFile),
+            Err(_) => Err(GzipError::CantReadFile)
+        }
+    }
+
+    fn position(&self) -> u64 {
+        self.pos
+    }
+}
@@ -7,7 +7,8 @@ pub struct VecBufSource {
     file: File,
     data: Vec<u8>,
     pos: usize,
-    size: usize
+    size: usize,
+    base: u64
 }
 
 const SIZE : usize = 32768;
@@ -15,23 +16,35 @@ const SIZE : usize = 32768;
 impl ByteSource for VecBufSource {
     fn get_u8(&mut self) -> GzipResult<u8> {
         if self.pos >= self.size {
+            self.base += self.size as u64;
             self.size = self.file
                 .read(&mut self.data)
                 .or(Err(GzipError::TruncatedFile))?;
             self.pos = 0;
+            if self.size == 0 {
+                return Err(GzipError::TruncatedFile);
+            }
         }
         let ans = self.data[self.pos];
         self.pos += 1;
         Ok(ans)
     }
+
+    fn available(&self) -> u64 {
+        self.size.saturating_sub(self.pos) as u64
+    }
+
+    fn position(&self) -> u64 {
+        self.base + self.pos as u64
+    }
 }
 
 impl VecBufSource {
     pub fn from_file(name: &str) -> GzipResult<Self> {
-        use GzipError::*;
+        use errors::GzipError::*;
         let data = vec![0; SIZE];
         let file = File::open(name).or(Err(CantOpenFile))?;
-        Ok(VecBufSource{ file, data, pos: 0, size: 0 })
+        Ok(VecBufSource{ file, data, pos: 0, size: 0, base: 0 })
     }
 }
 
@@ -18,11 +18,19 @@ impl ByteSource for VecSource {
         self.pos += 1;
         ans
     }
+
+    fn available(&self) -> u64 {
+        self.data.len().saturating_sub(self.pos) as u64
+    }
+
+    fn position(&self) -> u64 {
+        self.pos.min(self.data.len()) as u64
+    }
 }
 
 impl VecSource {
     pub fn from_file(name: &str) -> GzipResult<Self> {
-        use GzipError::*;
+        use errors::GzipError::*;
         let mut data = vec![];
         let mut file = File::open(name).or(Err(CantOpenFile))?;
         file.read_to_end(&mut data).or(Err(CantReadFile))?;
@@ -17,7 +17,7 @@ impl<'a> WideAdapter<'a> {
 impl<'a> BitSource for WideAdapter<'a> {
     fn get_bit(&mut self) -> GzipResult<u32> {
         if self.pos == 0 {
-            if let Ok(data) = self.data.get_u64() {
+            if let Some(data) = self.data.try_get_u64() {
                 self.cur = data;
                 self.pos = 64;
             } else {
@@ -31,6 +31,10 @@ impl<'a> BitSource for WideAdapter<'a> {
         Ok(ans as u32)
     }
 
+    fn bit_position(&self) -> u64 {
+        self.data.position() * 8 - self.pos as u64
+    }
+
     fn get_bits_rev(&mut self, size: u8) -> GzipResult<u32> {
         if size <= self.pos {
             let ans = self.cur & ((1 << size) - 1);
@@ -46,7 +50,7 @@ impl<'a> BitSource for WideAdapter<'a> {
 impl<'a> WideAdapter<'a> {
     fn get_bits_rev_slow(&mut self, size: u8) -> GzipResult<u32> {
         if self.pos > 0 {
-            if let Ok(data) = self.data.get_u64() {
+            if let Some(data) = self.data.try_get_u64() {
                 let mut ans = self.cur;
                 let left = size - self.pos;
                 ans |= (data & ((1 << left) - 1)) << self.pos;
@@ -77,6 +81,10 @@ impl<'a> ByteSource for WideAdapter<'a> {
             Ok(ans as u8)
         }
     }
+
+    fn position(&self) -> u64 {
+        self.bit_position().div_ceil(8)
+    }
 }
 
 
@@ -20,21 +20,25 @@ impl ByteSource for WideSource {
         ans
     }
 
-    fn get_u64(&mut self) -> GzipResult<u64> {
+    fn position(&self) -> u64 {
+        self.pos.min(self.data.len()) as u64
+    }
+
+    fn try_get_u64(&mut self) -> Option<u64> {
         if self.pos + 8 < self.data.len() {
             let small = &self.data[self.pos..self.pos + 8];
             let ans = unsafe { transmute::<&[u8], &[u64]>(small)[0].to_le() };
             self.pos += 8;
-            Ok(ans)
+            Some(ans)
         } else {
-            Err(GzipError::TruncatedFile)
+            None
         }
     }
 }
 
 impl WideSource {
     pub fn from_file(name: &str) -> GzipResult<Self> {
-        use GzipError::*;
+        use errors::GzipError::*;
         let mut data = vec![];
         let mut file = File::open(name).or(Err(CantOpenFile))?;
         file.read_to_end(&mut data).or(Err(CantReadFile))?;